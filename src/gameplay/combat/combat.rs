@@ -0,0 +1,104 @@
+use avian2d::{math::*, prelude::*};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::gameplay::equipment::hotbar::ActiveItem;
+use crate::gameplay::controller::plugin::ControllerInput;
+use crate::gameplay::input::input::{Action, TickActions};
+use crate::gameplay::item::items::ItemRegistry;
+use crate::gameplay::player::aim::AimTarget;
+use crate::gameplay::player::health::{apply_damage, Damage, Health};
+use crate::gameplay::player::player::Player;
+use crate::gameplay::player::setup::Layer;
+use crate::gameplay::stage::camera::CameraShake;
+use crate::gameplay::state::state::InGameState;
+use crate::gameplay::stealth::stealth::Noise;
+
+pub struct CombatPlugin;
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // Fired on the tick the trigger was pulled, and before anything reads this tick's damage
+            .add_systems(FixedUpdate, fire_weapon.after(ControllerInput).before(apply_damage).run_if(in_state(InGameState::Playing)));
+    }
+}
+
+// Per-weapon tuning read from the item data. Knockback is the velocity given to whatever is hit. 
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct WeaponStats {
+    pub damage: f32,
+    pub range: f32,
+    pub knockback: f32,
+    pub hit_stun: f32, // Seconds the target loses control
+    pub noise: f32, // Radius the shot can be heard from
+    #[serde(default)]
+    pub recoil: f32, // Camera trauma per shot
+}
+
+impl WeaponStats {
+    pub fn damage_along(&self, target: Entity, direction: Dir2) -> Damage {
+        Damage {
+            target,
+            amount: self.damage,
+            knockback: *direction * self.knockback,
+            stun: self.hit_stun,
+        }
+    }
+}
+
+// --- SYSTEMS ---
+// Hitscan from the player toward the aim target with whatever weapon is in hand. 
+pub fn fire_weapon(
+    tick: Res<TickActions>,
+    aim: Res<AimTarget>,
+    registry: Res<ItemRegistry>,
+    spatial_query: SpatialQuery,
+    player: Single<(Entity, &Transform, &ActiveItem), With<Player>>,
+    health_query: Query<(), With<Health>>,
+    mut damage_writer: MessageWriter<Damage>,
+    mut noise_writer: MessageWriter<Noise>,
+    mut shake_writer: MessageWriter<CameraShake>,
+) {
+    if !tick.state.just_pressed(Action::Fire) {
+        return;
+    }
+
+    let (entity, transform, active_item) = player.into_inner();
+    let Some(weapon) = active_item
+        .id
+        .as_deref()
+        .and_then(|id| registry.get(id))
+        .and_then(|definition| definition.weapon)
+    else {
+        return;
+    };
+
+    let origin = transform.translation.truncate();
+    let Ok(direction) = Dir2::new(aim.position - origin) else {
+        return;
+    };
+
+    noise_writer.write(Noise { source: entity, position: origin, radius: weapon.noise });
+    shake_writer.write(CameraShake(weapon.recoil));
+
+    let Some(target) = hitscan(&spatial_query, entity, origin, direction, &weapon) else {
+        return;
+    };
+
+    if health_query.contains(target) {
+        damage_writer.write(weapon.damage_along(target, direction));
+    }
+}
+
+// First body along the shot, shared by everything that carries a weapon
+pub fn hitscan(
+    spatial_query: &SpatialQuery,
+    shooter: Entity,
+    origin: Vec2,
+    direction: Dir2,
+    weapon: &WeaponStats,
+) -> Option<Entity> {
+    let filter = SpatialQueryFilter::from_mask([Layer::Default]).with_excluded_entities([shooter]);
+    spatial_query
+        .cast_ray(origin, direction, weapon.range as Scalar, true, &filter)
+        .map(|hit| hit.entity)
+}
@@ -1,44 +1,86 @@
 use avian2d::{math::*, prelude::*}; 
-use bevy::{ecs::query::Has, prelude::*};
-use crate::gameplay::player::player::Player;  
-use crate::gameplay::player::setup::Speed;
-use crate::gameplay::inventory::inventory::Searching;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::gameplay::input::input::{Action, TickActions};
+use crate::gameplay::inventory::encumbrance::Encumbrance;
+use crate::gameplay::player::aim::AimTarget;
+use crate::gameplay::player::health::{Damage, Invulnerable};
+use crate::gameplay::player::movement::{sneak, sprint, stop_sneak, stop_sprint};
+use crate::gameplay::player::player::{Player, PlayerStatus, Status};  
+use crate::gameplay::player::setup::{Layer, Speed};
+use crate::gameplay::player::stamina::Stamina;
+use crate::gameplay::replay::replay::replaying;
+use crate::gameplay::state::state::InGameState;
+use crate::gameplay::stealth::stealth::Footprint;
 
-const MAX_SPEED: f32 = 10.0; 
+const BASE_VELOCITY: Scalar = 250.0; // Target velocity at a Speed of 1.0
+
+// Collide and slide
+const MAX_SLIDES: usize = 4; // Surfaces resolved per step, enough for a corner plus a crate
+const SKIN: Scalar = 0.5; // Gap kept between the collider and whatever it touches
+
+// Dash tuning, all durations in seconds
+const DASH_STAMINA_COST: f32 = 20.0;
+const DASH_COOLDOWN: f32 = 0.8;
+const DASH_LOCKOUT: f32 = 0.2; // No steering while the dash carries the player
+const DASH_INVULNERABILITY: f32 = 0.3;
 
 pub struct PlayerControllerPlugin; 
 
 impl Plugin for PlayerControllerPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_message::<MovementAction>().add_systems(
-            Update, (
-                    keyboard_input, 
+            .add_message::<MovementAction>()
+            .add_systems(
+            FixedUpdate, (
+                    player_input.in_set(ControllerInput).run_if(not(replaying)), 
+                    tick_hit_stun,
+                    react_to_damage,
+                    dash,
                     movement, 
-                    apply_movement_damping
+                    apply_movement_damping,
+                    collide_and_slide,
                 )
-            .chain()); 
+            .chain()
+            .run_if(in_state(InGameState::Playing))); 
     }
 }
 
+// Turns input into MovementAction. Replays feed their recorded messages in place of this set. 
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ControllerInput;
+
 /* --- MESSAGES --- */
-#[derive(Message)]
+// Gait, Run, Sneak and Dash carry the move direction, at most unit length. 
+// Sneak is also sent while standing still so the player can crouch in place. 
+#[derive(Message, Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum MovementAction {
     Gait(Vec2), 
-    Jump,
     Run(Vec2), 
+    Sneak(Vec2),
+    Dash(Vec2),
 }
 
 /*  --- COMPONENTS --- */  
 #[derive(Component)]
 pub struct PlayerController; 
 
-// Marker for component indicating that entity is grounded. 
+// What the controller was asked to do this frame. Damping only applies while idle. 
+#[derive(Component, Default)]
+pub struct MovementIntent {
+    pub direction: Vector,
+    pub running: bool,
+    pub sneaking: bool,
+}
+
+// Input is ignored until the stun from the last hit wears off. 
 #[derive(Component)]
 #[component(storage = "SparseSet")]
-pub struct Grounded; 
+pub struct HitStun {
+    pub remaining: f32,
+}
 
-// The accleration used for player movement. 
+// How quickly velocity approaches the target velocity, in units per second squared. 
 #[derive(Component)]
 pub struct MovementAcceleration(Scalar); 
 
@@ -46,18 +88,32 @@ pub struct MovementAcceleration(Scalar);
 #[derive(Component)]
 pub struct MovementDampingFactor(Scalar); 
 
-// The strength of a jump. 
+// The dash burst and the timers that gate it. 
 #[derive(Component)]
-pub struct JumpImpulse(Scalar); 
+pub struct Dash {
+    pub impulse: Scalar,
+    pub cooldown: f32, // Time until the next dash is allowed
+    pub lockout: f32, // Time left before steering returns
+}
 
-// Bundle that contains the components needed for a basic 
-// dynamic character controller. 
+impl Dash {
+    pub const fn new(impulse: Scalar) -> Self {
+        Self { impulse, cooldown: 0.0, lockout: 0.0 }
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        self.lockout > 0.0
+    }
+}
+
+// Bundle that contains the components needed for a top-down kinematic character controller. 
+// LinearVelocity is the intended velocity, collide_and_slide trims it before physics moves the body. 
 #[derive(Bundle)]
 pub struct PlayerControllerBundle {
     player_controller: PlayerController, 
+    intent: MovementIntent, 
     body: RigidBody,
     collider: Collider, 
-    ground_caster: ShapeCaster, 
     locked_axes: LockedAxes, 
     movement: MovementBundle, 
 }
@@ -67,41 +123,36 @@ pub struct PlayerControllerBundle {
 pub struct MovementBundle {
     accleration: MovementAcceleration, 
     damping: MovementDampingFactor, 
-    jump_impulse: JumpImpulse,
+    dash: Dash,
 }
 
 impl MovementBundle {
     pub const fn new(
         accleration: Scalar,
         damping: Scalar,
-        jump_impulse: Scalar,
+        dash_impulse: Scalar,
     ) -> Self {
         Self {
             accleration: MovementAcceleration(accleration),
             damping: MovementDampingFactor(damping), 
-            jump_impulse: JumpImpulse(jump_impulse), 
+            dash: Dash::new(dash_impulse), 
         }
     }
 }
 
 impl Default for MovementBundle {
     fn default() -> Self {
-        Self::new(30.0, 0.9, 7.0)
+        Self::new(30.0, 0.9, 600.0)
     }
 }
 
 impl PlayerControllerBundle {
     pub fn new(collider: Collider) -> Self {
-        // Create shape caster as a slightly smaller version of collider
-        let mut caster_shape = collider.clone(); 
-        caster_shape.set_scale(Vector::ONE * 0.99, 10); 
-
         Self {
             player_controller: PlayerController,
-            body: RigidBody::Dynamic, 
+            intent: MovementIntent::default(),
+            body: RigidBody::Kinematic, 
             collider, 
-            ground_caster: ShapeCaster::new(caster_shape, Vector::ZERO, 0.0, Dir2::NEG_Y)
-                .with_max_distance(10.0), 
             locked_axes: LockedAxes::ROTATION_LOCKED, 
             movement: MovementBundle::default(), 
         }
@@ -111,80 +162,381 @@ impl PlayerControllerBundle {
         mut self,
         accleration: Scalar,
         damping: Scalar,
-        jump_impulse: Scalar,
+        dash_impulse: Scalar,
     ) -> Self {
-        self.movement = MovementBundle::new(accleration, damping, jump_impulse);
+        self.movement = MovementBundle::new(accleration, damping, dash_impulse);
         self 
     }
 }
 
-// Sends ['MovementAction'] events based on keyboard input. 
-// Remove Jump implementation and include up and down movement. 
-fn keyboard_input(
+// Sends ['MovementAction'] events based on the bound movement actions and the left stick. 
+fn player_input(
     mut movement_writer: MessageWriter<MovementAction>,
-    keyboard_input: Res<ButtonInput<KeyCode>>, 
+    tick: Res<TickActions>, 
+    aim: Res<AimTarget>,
+    transform: Single<&Transform, With<Player>>,
 ) {
-    let up = keyboard_input.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]); 
-    let down = keyboard_input.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]); 
-    let left = keyboard_input.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]); 
-    let right = keyboard_input.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]);
+    let action_state = &tick.state;
+    let up = action_state.pressed(Action::MoveUp); 
+    let down = action_state.pressed(Action::MoveDown); 
+    let left = action_state.pressed(Action::MoveLeft); 
+    let right = action_state.pressed(Action::MoveRight);
 
     let vertical = (up as i8 - down as i8) as Scalar;  
     let horizontal = (right as i8 - left as i8) as Scalar; 
-    let direction = Vec2::new(horizontal, vertical).normalize_or_zero(); // Ensure or_zero to prevent NaN clash with Avian
+    let digital = Vec2::new(horizontal, vertical).normalize_or_zero(); // Ensure or_zero to prevent NaN clash with Avian
 
-    if direction != Vec2::ZERO {
+    // The stick keeps its magnitude so a half tilt walks at half the acceleration
+    let analog = action_state.move_axis();
+    let direction = if analog != Vec2::ZERO { analog } else { digital };
+
+    if action_state.pressed(Action::Sneak) {
+        movement_writer.write(MovementAction::Sneak(direction)); 
+    }
+    else if direction != Vec2::ZERO {
         // DEBUG LINE: println!("direction: {:?}", direction); 
-        movement_writer.write(MovementAction::Gait(direction)); 
+        if action_state.pressed(Action::Sprint) {
+            movement_writer.write(MovementAction::Run(direction)); 
+        }
+        else {
+            movement_writer.write(MovementAction::Gait(direction)); 
+        }
     }
 
-    if keyboard_input.just_pressed(KeyCode::Space){
-        movement_writer.write(MovementAction::Jump); 
+    if action_state.just_pressed(Action::Dash) {
+        // Standing still dashes toward the aim target instead
+        let dash_direction = if direction != Vec2::ZERO {
+            direction.normalize_or_zero()
+        }
+        else {
+            (aim.position - transform.translation.truncate()).normalize_or_zero()
+        };
+
+        if dash_direction != Vec2::ZERO {
+            movement_writer.write(MovementAction::Dash(dash_direction)); 
+        }
     }
 }
 
-// Contains gamepad_inputs 
+fn tick_hit_stun(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut stun_query: Query<(Entity, &mut HitStun)>,
+) {
+    let delta_time = time.delta_secs_f64().adjust_precision(); 
+    for (entity, mut stun) in &mut stun_query {
+        stun.remaining -= delta_time;
+        if stun.remaining <= 0.0 {
+            commands.entity(entity).remove::<HitStun>();
+        }
+    }
+}
+
+// Knocks the controller back along the hit and stuns it. A fresh hit only extends the stun. 
+// Dashing through a hit leaves the player untouched, matching apply_damage. 
+fn react_to_damage(
+    mut commands: Commands,
+    mut damage_reader: MessageReader<Damage>,
+    mut controller_query: Query<(&mut LinearVelocity, Option<&mut HitStun>), (With<PlayerController>, Without<Invulnerable>)>,
+) {
+    for damage in damage_reader.read() {
+        let Ok((mut linear_velocity, stun)) = controller_query.get_mut(damage.target) else {
+            continue;
+        };
+
+        linear_velocity.0 += damage.knockback;
+        match stun {
+            Some(mut stun) => stun.remaining = stun.remaining.max(damage.stun),
+            None => {
+                commands.entity(damage.target).insert(HitStun { remaining: damage.stun });
+            }
+        }
+    }
+}
+
+// Spends stamina for a burst of velocity, a short steering lockout and a window of invulnerability. 
+fn dash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut movement_reader: MessageReader<MovementAction>,
+    controller: Single<(Entity, &mut Dash, &mut LinearVelocity, &mut Stamina, &PlayerStatus, Has<HitStun>), With<Player>>,
+) {
+    let delta_time = time.delta_secs_f64().adjust_precision(); 
+    let (entity, mut dash, mut linear_velocity, mut stamina, status, is_stunned) = controller.into_inner();
+    dash.cooldown = (dash.cooldown - delta_time).max(0.0);
+    dash.lockout = (dash.lockout - delta_time).max(0.0);
+
+    for event in movement_reader.read() {
+        let MovementAction::Dash(direction) = event else {
+            continue;
+        };
+
+        if is_stunned || dash.cooldown > 0.0 || status.condition != Status::Normal || stamina.current < DASH_STAMINA_COST {
+            continue;
+        }
+
+        stamina.current -= DASH_STAMINA_COST;
+        linear_velocity.0 = *direction * dash.impulse;
+        dash.cooldown = DASH_COOLDOWN;
+        dash.lockout = DASH_LOCKOUT;
+        commands.entity(entity).insert(Invulnerable { remaining: DASH_INVULNERABILITY });
+    }
+}
+
+// Gamepad and keyboard input both arrive as MovementAction 
+// Accelerates toward the target velocity. Run is a Gait that pays stamina for the sprint bonus 
+// and falls back to walking as soon as the player can no longer afford it. Sneak slows the 
+// target and shrinks the footprint. 
 fn movement(
     time: Res<Time>,
     mut movement_reader: MessageReader<MovementAction>,
-    mut controllers: Query<(&MovementAcceleration, &JumpImpulse, &mut LinearVelocity, Has<Grounded>), (With<Player>, Without<Searching>)>, 
-    speed: Single<&Speed, With<Player>>,
+    controller: Single<(
+        &MovementAcceleration,
+        &Dash,
+        &mut LinearVelocity,
+        &mut MovementIntent,
+        &mut Speed,
+        &mut Stamina,
+        &mut PlayerStatus,
+        &mut Footprint,
+        &Encumbrance,
+        Has<HitStun>,
+    ), With<Player>>,
 ) {
     // Precision is adjusted so that the example works with 
     // both 'f32' and 'f64' features. Otherwise remove this. 
+    // Time is Time<Fixed> here, so every step advances by the same amount. 
     let delta_time = time.delta_secs_f64().adjust_precision(); 
+    let (
+        movement_acceleration,
+        dash,
+        mut linear_velocity,
+        mut intent,
+        mut speed,
+        mut stamina,
+        mut status,
+        mut footprint,
+        encumbrance,
+        is_stunned,
+    ) = controller.into_inner();
 
+    let mut direction = Vector::ZERO;
+    let mut running = false;
+    let mut sneaking = false;
     for event in movement_reader.read() {
-        for (movement_acceleration, jump_impulse, mut linear_velocity, is_grounded) in controllers.iter_mut() {
-            match event {
-                MovementAction::Gait(direction) => {
-                    linear_velocity.x += direction.x * speed.current * movement_acceleration.0 * delta_time;
-                    linear_velocity.y += direction.y * speed.current * movement_acceleration.0 * delta_time; 
-                }
-                MovementAction::Jump => {
-                    if is_grounded {
-                        linear_velocity.y = jump_impulse.0; 
-                    }
-                }
-                _ => {}
+        match event {
+            MovementAction::Gait(gait) => {
+                direction = *gait;
+                (running, sneaking) = (false, false);
             }
+            MovementAction::Run(run) => {
+                direction = *run;
+                (running, sneaking) = (true, false);
+            }
+            MovementAction::Sneak(creep) => {
+                direction = *creep;
+                (running, sneaking) = (false, true);
+            }
+            MovementAction::Dash(_) => {}
         }
     }
+
+    if sneaking {
+        sneak(&mut speed, &mut footprint);
+    }
+    else {
+        stop_sneak(&mut speed, &mut footprint);
+    }
+
+    // Input is ignored until the dash lockout or hit stun runs out
+    if dash.is_dashing() || is_stunned {
+        direction = Vector::ZERO;
+        running = false;
+    }
+
+    if running && direction != Vector::ZERO {
+        running = sprint(&mut stamina, &mut speed, &mut status, encumbrance, delta_time);
+    }
+    else {
+        stop_sprint(&mut speed);
+    }
+
+    intent.direction = direction;
+    intent.running = running;
+    intent.sneaking = sneaking;
+    if direction == Vector::ZERO {
+        return;
+    }
+
+    // The sprint bonus was just set, so apply the stack here instead of waiting for StatsSystems in Update
+    let target = direction * speed.modifiers.apply(speed.base) * BASE_VELOCITY;
+    linear_velocity.0 = linear_velocity.0.move_towards(target, movement_acceleration.0 * delta_time);
 }
 
-// Slows down movement in the X direction. 
+// Slows the controller down once there is no movement input. 
+// A dash keeps its full burst until the lockout ends. 
 fn apply_movement_damping(
     time: Res<Time>, 
-    mut query: Query<(&MovementDampingFactor, &mut LinearVelocity)>, 
-    speed: Single<&Speed, With<Player>>,
+    mut query: Query<(&MovementDampingFactor, &MovementIntent, &Dash, &mut LinearVelocity)>, 
 ) {
     // Precision is adjusted
     let delta_time = time.delta_secs_f64().adjust_precision(); 
-    let speed_ratio = (speed.current / MAX_SPEED).min(1.0);
-    let damping_multiplier = 1.0 - speed_ratio;
 
-    for (damping_factor, mut linear_velocity) in query.iter_mut() {
-        linear_velocity.x *= 1.0 / (1.0 + damping_factor.0 * damping_multiplier * delta_time); 
-        linear_velocity.y *= 1.0 / (1.0 + damping_factor.0 * damping_multiplier * delta_time);
+    for (damping_factor, intent, dash, mut linear_velocity) in query.iter_mut() {
+        if intent.direction != Vector::ZERO || dash.is_dashing() {
+            continue;
+        }
+
+        linear_velocity.x *= 1.0 / (1.0 + damping_factor.0 * delta_time); 
+        linear_velocity.y *= 1.0 / (1.0 + damping_factor.0 * delta_time);
+    }
+}
+
+// Sweeps the collider along this step's velocity and slides along whatever it hits. 
+// The circle collider glances off rectangle corners instead of catching on them. 
+// Dynamic bodies in the way are shoved by the share of the motion the player's mass wins, 
+// so a light crate is pushed almost at walking speed and a heavy one barely moves. 
+fn collide_and_slide(
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    controller: Single<(Entity, &Collider, &Position, &Rotation, &ComputedMass, &mut LinearVelocity), With<PlayerController>>,
+    mut body_query: Query<(&RigidBody, &ComputedMass, &mut LinearVelocity), Without<PlayerController>>,
+) {
+    let delta_time = time.delta_secs_f64().adjust_precision(); 
+    let (entity, collider, position, rotation, mass, mut linear_velocity) = controller.into_inner();
+    if delta_time <= 0.0 || linear_velocity.0 == Vector::ZERO {
+        return;
     }
-}
\ No newline at end of file
+
+    let filter = SpatialQueryFilter::from_mask([Layer::Default]).with_excluded_entities([entity]);
+    let mut remaining = linear_velocity.0 * delta_time;
+    let mut displacement = Vector::ZERO;
+
+    for _ in 0..MAX_SLIDES {
+        let Ok(direction) = Dir2::new(remaining) else {
+            break;
+        };
+        let distance = remaining.length();
+        let config = ShapeCastConfig::from_max_distance(distance + SKIN);
+
+        let Some(hit) = spatial_query.cast_shape(collider, position.0 + displacement, rotation.as_radians(), direction, &config, &filter) else {
+            displacement += remaining;
+            break;
+        };
+
+        // Move up to the surface, keeping the skin gap
+        let travel = (hit.distance - SKIN).clamp(0.0, distance);
+        displacement += direction * travel;
+        remaining -= direction * travel;
+
+        let normal = hit.normal1;
+        let into = remaining.dot(normal);
+        if into >= 0.0 {
+            continue;
+        }
+
+        // The share of the blocked motion that the pusher's mass carries through
+        let mut carried = 0.0;
+        if let Ok((body, body_mass, mut body_velocity)) = body_query.get_mut(hit.entity) {
+            if body.is_dynamic() {
+                carried = mass.value() / (mass.value() + body_mass.value());
+                let push_speed = -into / delta_time * carried;
+                let current = body_velocity.0.dot(-normal);
+                if current < push_speed {
+                    body_velocity.0 += -normal * (push_speed - current);
+                }
+            }
+        }
+
+        remaining -= normal * into * (1.0 - carried);
+    }
+
+    linear_velocity.0 = displacement / delta_time;
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+    use bevy::{state::app::StatesPlugin, time::TimeUpdateStrategy};
+    use super::*;
+    use crate::gameplay::input::input::{ActionSnapshot, ActionState};
+    use crate::gameplay::player::aim::{AimSource, VisibilityCone};
+    use crate::gameplay::replay::replay::Replay;
+    use crate::gameplay::state::state::GameState;
+    use crate::gameplay::stats::modifiers::ModifierStack;
+
+    // A whole number of frames at every rate below, and well clear of a fixed tick boundary
+    pub const SIMULATED_SECONDS: f64 = 13.0 / 6.0;
+
+    // Headless app running the controller mid-raid, with every frame lasting 1/fps seconds
+    pub fn controller_app(fps: f64) -> App {
+        let mut app = App::new();
+        app
+            .add_plugins((
+                MinimalPlugins,
+                StatesPlugin,
+                TransformPlugin,
+                AssetPlugin::default(),
+                bevy::scene::ScenePlugin,
+                PhysicsPlugins::default().with_length_unit(50.0),
+                PlayerControllerPlugin,
+            ))
+            .init_asset::<Mesh>()
+            .insert_state(GameState::InGame)
+            .add_sub_state::<InGameState>()
+            .add_message::<Damage>()
+            .init_resource::<TickActions>()
+            .insert_resource(Gravity(Vector::ZERO))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / fps)))
+            .insert_resource(Replay::default())
+            .insert_resource(AimTarget { position: Vec2::ZERO, source: AimSource::Mouse, stick_direction: Vec2::ZERO });
+        app
+    }
+
+    // Same tuning as spawn_player
+    pub fn spawn_test_player(app: &mut App) -> Entity {
+        app.world_mut().spawn((
+            Player,
+            PlayerControllerBundle::new(Collider::circle(30.0)).with_movement(1500.0, 5.0, 900.0),
+            Speed { base: 1.0, current: 1.0, modifiers: ModifierStack::default() },
+            Stamina { base: 100.0, max: 100.0, current: 100.0, modifiers: ModifierStack::default() },
+            PlayerStatus { condition: Status::Normal, duration: 0.0 },
+            Footprint::default(),
+            Encumbrance::default(),
+            VisibilityCone { range: 1000.0, angle: 90.0_f32.to_radians(), direction: Vec2::Y },
+            Transform::default(),
+        )).id()
+    }
+
+    // Counted on the clock rather than in frames, in case the first frame has no delta
+    pub fn run_for(app: &mut App, seconds: f64) {
+        while app.world().resource::<Time<Virtual>>().elapsed_secs_f64() < seconds - 1e-6 {
+            app.update();
+        }
+    }
+
+    // Sprints right for SIMULATED_SECONDS at the given frame rate and returns where the player ended up
+    fn simulate(fps: f64) -> (Vector, f32) {
+        let mut app = controller_app(fps);
+        app.world_mut().resource_mut::<TickActions>().state = ActionState::from_snapshot(&ActionSnapshot {
+            pressed: vec![Action::MoveRight, Action::Sprint],
+            ..default()
+        });
+        let player = spawn_test_player(&mut app);
+        run_for(&mut app, SIMULATED_SECONDS);
+
+        let world = app.world();
+        (world.get::<Position>(player).unwrap().0, world.get::<Stamina>(player).unwrap().current)
+    }
+
+    #[test]
+    fn frame_rate_does_not_change_the_result() {
+        let (position, stamina) = simulate(60.0);
+        assert!(position.x > 0.0, "the player never moved");
+        assert!(stamina < 100.0, "sprinting never cost stamina");
+
+        for fps in [30.0, 144.0] {
+            assert_eq!(simulate(fps), (position, stamina), "{} FPS diverged from 60 FPS", fps);
+        }
+    }
+}
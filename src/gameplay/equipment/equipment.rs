@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::gameplay::combat::combat::fire_weapon;
+use crate::gameplay::equipment::{hotbar::*, ui::*};
+use crate::gameplay::inventory::{inventory::Inventory, ui::setup_ui};
+use crate::gameplay::item::items::{Item, ItemRegistry, ItemType};
+use crate::gameplay::player::player::Player;
+use crate::gameplay::state::state::{GameState, InGameState};
+
+pub struct EquipmentPlugin;
+impl Plugin for EquipmentPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_message::<EquipRequest>()
+            .init_resource::<InventoryFocus>()
+            .add_systems(OnEnter(GameState::InGame), spawn_equipment_ui.after(setup_ui))
+            .add_systems(Update, (
+                click_inventory_item,
+                click_equipment_slot,
+                navigate_inventory.run_if(in_state(InGameState::Inventory)),
+                highlight_inventory_focus.after(navigate_inventory).after(update_inventory_list),
+                handle_equip_requests
+                    .after(click_inventory_item)
+                    .after(click_equipment_slot)
+                    .after(navigate_inventory),
+                update_active_item.after(handle_equip_requests),
+                update_inventory_list,
+                update_equipment_ui,
+                update_hotbar_ui,
+            ).run_if(in_state(GameState::InGame)))
+            // Switching weapons is part of the simulation, so a shot on the next tick uses the new one
+            .add_systems(FixedUpdate, (
+                select_hotbar_slot,
+                update_active_item,
+            ).chain().before(fire_weapon).run_if(in_state(InGameState::Playing)));
+    }
+}
+
+// --- ENUMS ---
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum EquipSlot {
+    Holster,
+    LongGun,
+    Hat,
+    Vest,
+    Belt,
+}
+
+impl EquipSlot {
+    pub const ALL: [EquipSlot; 5] = [
+        EquipSlot::Holster,
+        EquipSlot::LongGun,
+        EquipSlot::Hat,
+        EquipSlot::Vest,
+        EquipSlot::Belt,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EquipSlot::Holster => "Holster",
+            EquipSlot::LongGun => "Long Gun",
+            EquipSlot::Hat => "Hat",
+            EquipSlot::Vest => "Vest",
+            EquipSlot::Belt => "Belt",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EquipError {
+    UnknownItem,
+    NotEquipment,
+    WrongSlot,
+    NotCarried,
+    EmptySlot,
+}
+
+impl EquipError {
+    // Shown under the equipment slots
+    pub fn message(&self) -> &'static str {
+        match self {
+            EquipError::UnknownItem => "Unknown item",
+            EquipError::NotEquipment => "That can't be equipped",
+            EquipError::WrongSlot => "That doesn't go in this slot",
+            EquipError::NotCarried => "You aren't carrying that",
+            EquipError::EmptySlot => "Nothing to take off",
+        }
+    }
+}
+
+// --- MESSAGES ---
+#[derive(Message)]
+pub enum EquipRequest {
+    Equip { item_id: String, slot: EquipSlot },
+    Unequip(EquipSlot),
+}
+
+// --- COMPONENTS ---
+#[derive(Component, Default, Serialize, Deserialize, Clone, Debug)]
+pub struct Equipment {
+    pub slots: BTreeMap<EquipSlot, Item>,
+}
+
+impl Equipment {
+    pub fn get(&self, slot: EquipSlot) -> Option<&Item> {
+        self.slots.get(&slot)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.slots.values().any(|item| item.id == id)
+    }
+
+    pub fn weight(&self, registry: &ItemRegistry) -> f32 {
+        self.slots
+            .values()
+            .filter_map(|item| registry.get(&item.id).map(|def| def.weight * item.stack as f32))
+            .sum()
+    }
+
+    // Moves an item from the inventory into its slot. Whatever was in the slot goes back to the inventory.
+    pub fn equip(
+        &mut self,
+        inventory: &mut Inventory,
+        registry: &ItemRegistry,
+        item_id: &str,
+        slot: EquipSlot,
+    ) -> Result<(), EquipError> {
+        let definition = registry.get(item_id).ok_or(EquipError::UnknownItem)?;
+        if definition.item_type != ItemType::Equipment {
+            return Err(EquipError::NotEquipment);
+        }
+        if definition.slot != Some(slot) {
+            return Err(EquipError::WrongSlot);
+        }
+
+        let item = inventory.take(item_id).ok_or(EquipError::NotCarried)?;
+        if let Some(previous) = self.slots.insert(slot, item) {
+            inventory.add(previous);
+        }
+        Ok(())
+    }
+
+    pub fn unequip(&mut self, inventory: &mut Inventory, slot: EquipSlot) -> Result<(), EquipError> {
+        let item = self.slots.remove(&slot).ok_or(EquipError::EmptySlot)?;
+        inventory.add(item);
+        Ok(())
+    }
+}
+
+// --- SYSTEMS ---
+fn handle_equip_requests(
+    mut requests: MessageReader<EquipRequest>,
+    registry: Res<ItemRegistry>,
+    player: Single<(&mut Inventory, &mut Equipment, &mut Hotbar), With<Player>>,
+    mut status_query: Query<&mut Text, With<EquipStatusText>>,
+) {
+    let (mut inventory, mut equipment, mut hotbar) = player.into_inner();
+
+    for request in requests.read() {
+        let result = match request {
+            EquipRequest::Equip { item_id, slot } => equipment
+                .equip(&mut inventory, &registry, item_id, *slot)
+                .map(|_| { hotbar.assign_free(item_id); }),
+            EquipRequest::Unequip(slot) => equipment.unequip(&mut inventory, *slot),
+        };
+
+        // The last request decides the message, a success clears an old failure
+        let message = result.as_ref().err().map(EquipError::message).unwrap_or("");
+        for mut text in &mut status_query {
+            text.0 = message.to_string();
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::gameplay::equipment::equipment::Equipment;
+use crate::gameplay::input::input::{Action, TickActions};
+use crate::gameplay::inventory::inventory::Inventory;
+use crate::gameplay::player::player::Player;
+
+pub const HOTBAR_SIZE: usize = 5;
+
+// --- COMPONENTS ---
+// Slots hold item ids, not items. The items themselves stay in the Inventory or Equipment.
+#[derive(Component, Default, Serialize, Deserialize, Clone, Debug)]
+pub struct Hotbar {
+    pub slots: [Option<String>; HOTBAR_SIZE],
+    pub selected: Option<usize>,
+}
+
+impl Hotbar {
+    pub fn assign(&mut self, index: usize, id: &str) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            *slot = Some(id.to_string());
+        }
+    }
+
+    // Puts the id in the first empty slot unless it is already on the hotbar.
+    pub fn assign_free(&mut self, id: &str) -> Option<usize> {
+        if let Some(index) = self.slots.iter().position(|slot| slot.as_deref() == Some(id)) {
+            return Some(index);
+        }
+
+        let index = self.slots.iter().position(|slot| slot.is_none())?;
+        self.assign(index, id);
+        Some(index)
+    }
+}
+
+// The item the player currently has in hand, chosen through the hotbar.
+#[derive(Component, Default, Debug)]
+pub struct ActiveItem {
+    pub id: Option<String>,
+}
+
+// --- SYSTEMS ---
+pub fn select_hotbar_slot(
+    tick: Res<TickActions>,
+    mut hotbar: Single<&mut Hotbar, With<Player>>,
+) {
+    for index in 0..HOTBAR_SIZE {
+        if tick.state.just_pressed(Action::Hotbar(index as u8)) {
+            // Pressing the selected number again holsters the item
+            hotbar.selected = if hotbar.selected == Some(index) { None } else { Some(index) };
+        }
+    }
+}
+
+pub fn update_active_item(
+    player: Single<(&Hotbar, &Inventory, &Equipment, &mut ActiveItem), With<Player>>,
+) {
+    let (hotbar, inventory, equipment, mut active_item) = player.into_inner();
+
+    // A slot only counts while the player still carries what it points at
+    let id = hotbar
+        .selected
+        .and_then(|index| hotbar.slots[index].clone())
+        .filter(|id| inventory.contains(id) || equipment.contains(id));
+
+    if active_item.id != id {
+        active_item.id = id;
+    }
+}
@@ -0,0 +1,3 @@
+pub mod equipment;
+pub mod hotbar;
+pub mod ui;
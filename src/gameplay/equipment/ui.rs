@@ -0,0 +1,277 @@
+use bevy::prelude::*;
+use crate::gameplay::equipment::{
+    equipment::{EquipRequest, EquipSlot, Equipment},
+    hotbar::{Hotbar, HOTBAR_SIZE},
+};
+use crate::gameplay::input::input::{Action, ActionState};
+use crate::gameplay::inventory::{inventory::Inventory, ui::{InventoryUi, MainStorage, INVENTORY_COLUMNS}};
+use crate::gameplay::item::items::ItemRegistry;
+use crate::gameplay::player::player::Player;
+
+const FONT_SIZE: f32 = 15.0;
+const SLOT_COLOR: Color = Color::srgba(0.2, 0.2, 0.2, 0.8);
+const SELECTED_COLOR: Color = Color::srgba(0.7, 0.5, 0.2, 0.9);
+const FOCUS_COLOR: Color = Color::srgba(0.3, 0.45, 0.7, 0.9);
+const ERROR_COLOR: Color = Color::srgb(0.9, 0.3, 0.25);
+
+// --- RESOURCES ---
+// Grid cell picked with the d-pad or arrow keys while the inventory is open.
+#[derive(Resource, Default)]
+pub struct InventoryFocus {
+    pub index: Option<usize>,
+}
+
+// --- COMPONENTS ---
+#[derive(Component)]
+pub struct EquipmentSlotUi(pub EquipSlot);
+
+#[derive(Component)]
+pub struct EquipmentSlotText(pub EquipSlot);
+
+// Why the last equip or unequip did nothing, empty otherwise
+#[derive(Component)]
+pub struct EquipStatusText;
+
+#[derive(Component)]
+pub struct HotbarSlotUi(pub usize);
+
+#[derive(Component)]
+pub struct HotbarSlotText(pub usize);
+
+// Button for a single inventory entry inside MainStorage, `index` is its cell in the grid
+#[derive(Component)]
+pub struct InventoryItemButton {
+    pub id: String,
+    pub index: usize,
+}
+
+// --- SYSTEMS ---
+pub fn spawn_equipment_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    inventory_ui: Single<Entity, With<InventoryUi>>,
+) {
+    let font_handle: Handle<Font> = asset_server.load("fonts/ztn.otf");
+    let text_font = TextFont {
+        font: font_handle,
+        font_size: FONT_SIZE,
+        ..default()
+    };
+
+    commands.entity(*inventory_ui).with_children(|parent| {
+        // Equipment column left of the main storage
+        parent
+            .spawn(Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(1.0),
+                top: Val::Percent(2.5),
+                width: Val::Percent(13.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            })
+            .with_children(|column| {
+                for slot in EquipSlot::ALL {
+                    column.spawn((
+                        Button,
+                        Node {
+                            padding: UiRect::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(SLOT_COLOR),
+                        EquipmentSlotUi(slot),
+                        children![(
+                            Text::new(format!("{}: -", slot.label())),
+                            text_font.clone(),
+                            TextColor(Color::WHITE),
+                            EquipmentSlotText(slot),
+                        )],
+                    ));
+                }
+                column.spawn((Text::new(""), text_font.clone(), TextColor(ERROR_COLOR), EquipStatusText));
+            });
+
+        // Numbered hotbar under the main storage
+        parent
+            .spawn(Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(15.0),
+                top: Val::Percent(72.0),
+                width: Val::Percent(70.0),
+                column_gap: Val::Px(4.0),
+                ..default()
+            })
+            .with_children(|row| {
+                for index in 0..HOTBAR_SIZE {
+                    row.spawn((
+                        Node {
+                            flex_grow: 1.0,
+                            padding: UiRect::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(SLOT_COLOR),
+                        HotbarSlotUi(index),
+                        children![(
+                            Text::new(format!("{}: -", index + 1)),
+                            text_font.clone(),
+                            TextColor(Color::WHITE),
+                            HotbarSlotText(index),
+                        )],
+                    ));
+                }
+            });
+    });
+}
+
+// Rebuilds the item buttons inside MainStorage whenever the inventory changes.
+pub fn update_inventory_list(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    inventory: Single<&Inventory, (With<Player>, Changed<Inventory>)>,
+    storage: Single<Entity, With<MainStorage>>,
+) {
+    let font_handle: Handle<Font> = asset_server.load("fonts/ztn.otf");
+
+    commands.entity(*storage).despawn_related::<Children>();
+    commands.entity(*storage).with_children(|parent| {
+        for (index, item) in inventory.items.iter().enumerate() {
+            parent.spawn((
+                Button,
+                Node {
+                    margin: UiRect::all(Val::Px(4.0)),
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                BackgroundColor(SLOT_COLOR),
+                InventoryItemButton { id: item.id.clone(), index },
+                children![(
+                    Text::new(format!("{} x{}{}", item.id, item.stack, if item.insured { " (insured)" } else { "" })),
+                    TextFont {
+                        font: font_handle.clone(),
+                        font_size: FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                )],
+            ));
+        }
+    });
+}
+
+pub fn click_inventory_item(
+    registry: Res<ItemRegistry>,
+    button_query: Query<(&Interaction, &InventoryItemButton), Changed<Interaction>>,
+    mut hotbar: Single<&mut Hotbar, With<Player>>,
+    mut equip_writer: MessageWriter<EquipRequest>,
+) {
+    for (interaction, button) in &button_query {
+        if *interaction == Interaction::Pressed {
+            use_inventory_item(&button.id, &registry, &mut hotbar, &mut equip_writer);
+        }
+    }
+}
+
+// Moves the focus around the grid and uses the focused item on Confirm.
+pub fn navigate_inventory(
+    action_state: Res<ActionState>,
+    registry: Res<ItemRegistry>,
+    inventory: Single<&Inventory, With<Player>>,
+    mut hotbar: Single<&mut Hotbar, With<Player>>,
+    mut focus: ResMut<InventoryFocus>,
+    mut equip_writer: MessageWriter<EquipRequest>,
+) {
+    let count = inventory.items.len();
+    if count == 0 {
+        focus.index = None;
+        return;
+    }
+
+    let columns = INVENTORY_COLUMNS as isize;
+    let step = if action_state.just_pressed(Action::NavigateLeft) { -1 }
+        else if action_state.just_pressed(Action::NavigateRight) { 1 }
+        else if action_state.just_pressed(Action::NavigateUp) { -columns }
+        else if action_state.just_pressed(Action::NavigateDown) { columns }
+        else { 0 };
+
+    if step != 0 {
+        focus.index = Some(match focus.index {
+            Some(index) => (index as isize + step).clamp(0, count as isize - 1) as usize,
+            None => 0,
+        });
+    }
+    else if let Some(index) = focus.index.filter(|index| *index >= count) {
+        // The item under the focus was equipped or dropped
+        focus.index = Some(index.min(count - 1));
+    }
+
+    if action_state.just_pressed(Action::Confirm) {
+        if let Some(item) = focus.index.and_then(|index| inventory.items.get(index)) {
+            use_inventory_item(&item.id, &registry, &mut hotbar, &mut equip_writer);
+        }
+    }
+}
+
+pub fn highlight_inventory_focus(
+    focus: Res<InventoryFocus>,
+    mut button_query: Query<(&InventoryItemButton, &mut BackgroundColor)>,
+) {
+    for (button, mut background) in &mut button_query {
+        let color = if focus.index == Some(button.index) { FOCUS_COLOR } else { SLOT_COLOR };
+        if background.0 != color {
+            background.0 = color;
+        }
+    }
+}
+
+// Equipment goes to its slot, anything else is put on the hotbar.
+fn use_inventory_item(
+    id: &str,
+    registry: &ItemRegistry,
+    hotbar: &mut Hotbar,
+    equip_writer: &mut MessageWriter<EquipRequest>,
+) {
+    match registry.get(id).and_then(|def| def.slot) {
+        Some(slot) => {
+            equip_writer.write(EquipRequest::Equip { item_id: id.to_string(), slot });
+        }
+        None => {
+            hotbar.assign_free(id);
+        }
+    }
+}
+
+pub fn click_equipment_slot(
+    button_query: Query<(&Interaction, &EquipmentSlotUi), Changed<Interaction>>,
+    mut equip_writer: MessageWriter<EquipRequest>,
+) {
+    for (interaction, slot_ui) in &button_query {
+        if *interaction == Interaction::Pressed {
+            equip_writer.write(EquipRequest::Unequip(slot_ui.0));
+        }
+    }
+}
+
+pub fn update_equipment_ui(
+    equipment: Single<&Equipment, (With<Player>, Changed<Equipment>)>,
+    mut text_query: Query<(&EquipmentSlotText, &mut Text)>,
+) {
+    for (slot_text, mut text) in &mut text_query {
+        let name = equipment.get(slot_text.0).map(|item| item.id.as_str()).unwrap_or("-");
+        text.0 = format!("{}: {}", slot_text.0.label(), name);
+    }
+}
+
+pub fn update_hotbar_ui(
+    hotbar: Single<&Hotbar, (With<Player>, Changed<Hotbar>)>,
+    mut text_query: Query<(&HotbarSlotText, &mut Text)>,
+    mut slot_query: Query<(&HotbarSlotUi, &mut BackgroundColor)>,
+) {
+    for (slot_text, mut text) in &mut text_query {
+        let name = hotbar.slots[slot_text.0].as_deref().unwrap_or("-");
+        text.0 = format!("{}: {}", slot_text.0 + 1, name);
+    }
+
+    for (slot_ui, mut background) in &mut slot_query {
+        background.0 = if hotbar.selected == Some(slot_ui.0) { SELECTED_COLOR } else { SLOT_COLOR };
+    }
+}
@@ -1,24 +1,62 @@
 use crate::gameplay::{
+    combat::combat::CombatPlugin,
     cursor::cursor::CursorPlugin,
+    equipment::equipment::EquipmentPlugin,
     gizmos::gizmos::GizmosPlugin,
+    input::input::InputPlugin,
     inventory::inventory::InventoryPlugin,
     item::items::ItemPlugin,
+    navigation::navigation::NavigationPlugin,
+    npc::npc::NpcPlugin,
     player::player::PlayerPlugin, 
+    raid::raid::RaidPlugin,
+    replay::replay::ReplayPlugin,
+    save::save::SavePlugin,
     stage::stage::StagePlugin,
+    stash::stash::StashPlugin,
+    state::state::GameStatePlugin,
+    stats::stats::StatsPlugin,
+    stealth::stealth::StealthPlugin,
 };
 use bevy::prelude::*;
 
-pub struct GameplayPlugin;
+// Command line flags, read once in main
+#[derive(Clone, Debug, Default)]
+pub struct LaunchOptions {
+    pub level: Option<String>, // `--level <name>`
+    pub seed: Option<u64>, // `--seed <n>`
+    pub record: bool, // `--record`
+    pub replay: Option<String>, // `--replay <file>`
+}
+
+pub struct GameplayPlugin {
+    pub options: LaunchOptions,
+}
+
 impl Plugin for GameplayPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_plugins((
-                StagePlugin, 
+                GameStatePlugin,
+                InputPlugin,
+                StagePlugin { level: self.options.level.clone() },
                 CursorPlugin,
                 PlayerPlugin, 
                 ItemPlugin,
                 InventoryPlugin,
+                EquipmentPlugin,
                 GizmosPlugin,
+                StatsPlugin,
+                SavePlugin,
+                StealthPlugin,
+                CombatPlugin,
+                ReplayPlugin {
+                    record: self.options.record,
+                    replay: self.options.replay.clone(),
+                    seed: self.options.seed,
+                },
+                // Bevy takes at most 15 plugins per tuple
+                (RaidPlugin, StashPlugin, NpcPlugin, NavigationPlugin),
             )); 
     }
 }
@@ -1,12 +1,23 @@
 use bevy::prelude::*;
 
+use crate::gameplay::navigation::navigation::NavAgent;
+use crate::gameplay::npc::ai::{AiState, Brain};
+use crate::gameplay::npc::perception::Perception;
+use crate::gameplay::player::aim::VisibilityCone;
 use crate::gameplay::player::setup::InteractionSensor;
+use crate::gameplay::state::state::GameState;
+use crate::gameplay::stealth::stealth::Noise;
 
 pub struct GizmosPlugin; 
 impl Plugin for GizmosPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_systems(Update, draw_interaction_sensors); 
+            .add_systems(Update, (
+                draw_interaction_sensors,
+                draw_noise,
+                draw_npc_vision,
+                draw_paths,
+            ).run_if(in_state(GameState::InGame))); 
     }
 }
 
@@ -21,4 +32,52 @@ fn draw_interaction_sensors(
             Color::srgba(0.0, 1.0, 0.0, 0.3),
         );
     } 
-}
\ No newline at end of file
+}
+
+fn draw_noise(mut gizmos: Gizmos, mut noise_reader: MessageReader<Noise>) {
+    for noise in noise_reader.read() {
+        gizmos.circle_2d(noise.position, noise.radius, Color::srgba(1.0, 0.8, 0.0, 0.3));
+    }
+}
+
+// Each NPC's vision cone, tinted by what its brain is doing, with its suspicion as a bar overhead
+fn draw_npc_vision(mut gizmos: Gizmos, npc_query: Query<(&Transform, &VisibilityCone, &Brain, &Perception)>) {
+    for (transform, cone, brain, perception) in &npc_query {
+        let color = match brain.state {
+            AiState::Idle | AiState::Patrol => Color::srgba(0.6, 0.6, 0.6, 0.3),
+            AiState::Investigate => Color::srgba(1.0, 0.8, 0.0, 0.4),
+            AiState::Combat => Color::srgba(1.0, 0.1, 0.1, 0.5),
+            AiState::Flee => Color::srgba(0.3, 0.5, 1.0, 0.4),
+        };
+
+        let position = transform.translation.truncate();
+        let half_angle = cone.angle * 0.5;
+        let facing = Rot2::radians(Vec2::Y.angle_to(cone.direction));
+        gizmos.arc_2d(Isometry2d::new(position, facing), cone.angle, cone.range, color);
+        for edge in [-half_angle, half_angle] {
+            gizmos.line_2d(position, position + Rot2::radians(edge) * cone.direction * cone.range, color);
+        }
+
+        if perception.suspicion > 0.0 {
+            let start = position + Vec2::new(-30.0, 45.0);
+            let alarm = Color::srgb(1.0, 1.0 - perception.suspicion, 0.0);
+            gizmos.line_2d(start, start + Vec2::X * 60.0 * perception.suspicion, alarm);
+        }
+    }
+}
+
+// Where each agent is headed, flow field paths in a different colour from A* ones
+fn draw_paths(mut gizmos: Gizmos, agent_query: Query<(&Transform, &NavAgent)>) {
+    for (transform, agent) in &agent_query {
+        if agent.path.is_empty() {
+            continue;
+        }
+
+        let color = if agent.flow { Color::srgba(0.9, 0.3, 0.9, 0.6) } else { Color::srgba(0.2, 0.8, 0.9, 0.6) };
+        let points = std::iter::once(transform.translation.truncate()).chain(agent.path.iter().copied());
+        gizmos.linestrip_2d(points, color);
+        for point in &agent.path {
+            gizmos.circle_2d(*point, 6.0, color);
+        }
+    }
+}
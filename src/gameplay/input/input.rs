@@ -0,0 +1,527 @@
+use std::{collections::{BTreeMap, HashSet}, fs, path::Path};
+use bevy::{input::InputSystems, prelude::*};
+use serde::{Deserialize, Serialize};
+use crate::gameplay::equipment::hotbar::HOTBAR_SIZE;
+use crate::gameplay::input::rebind::*;
+use crate::gameplay::replay::replay::replaying;
+use crate::gameplay::state::state::InGameState;
+
+const CONFIG_DIR: &str = "config";
+const BINDINGS_FILE: &str = "config/bindings.ron";
+pub const MAX_BINDINGS: usize = 3; // Two keyboard/mouse bindings and one gamepad binding per action
+const STICK_DEADZONE: f32 = 0.15;
+const STICK_OUTER: f32 = 0.95;
+
+pub struct InputPlugin;
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(load_bindings())
+            .init_resource::<ActionState>()
+            .init_resource::<TickActions>()
+            .init_resource::<Rebinding>()
+            .add_systems(PreUpdate, (update_action_state, collect_transitions).chain().after(InputSystems).run_if(not(replaying)))
+            .add_systems(OnEnter(InGameState::Playing), clear_pending_transitions)
+            .add_systems(FixedPreUpdate, begin_tick.run_if(in_state(InGameState::Playing).and(not(replaying))))
+            .add_systems(OnEnter(InGameState::Paused), spawn_rebind_ui)
+            .add_systems(OnExit(InGameState::Paused), cancel_rebinding)
+            .add_systems(Update, (
+                click_binding,
+                capture_binding,
+                update_rebind_ui,
+            ).chain().run_if(in_state(InGameState::Paused)));
+    }
+}
+
+// --- ACTIONS ---
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Sprint,
+    Sneak,
+    #[serde(alias = "Jump")] // Bindings saved before the dash replaced jumping
+    Dash,
+    Interact,
+    Inventory,
+    CycleTarget,
+    Fire,
+    Reload,
+    Hotbar(u8),
+    Pause,
+    Confirm,
+    QuitToMenu,
+    QuickSave,
+    QuickLoad,
+    CycleSaveSlot,
+    NavigateUp,
+    NavigateDown,
+    NavigateLeft,
+    NavigateRight,
+    Insure,
+    CycleFilter,
+    SortStash,
+    StartRaid,
+}
+
+// Bindings only conflict with actions that can be active at the same time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionContext {
+    Gameplay,
+    Menu,
+}
+
+impl Action {
+    pub fn all() -> Vec<Action> {
+        let mut actions = vec![
+            Action::MoveUp,
+            Action::MoveDown,
+            Action::MoveLeft,
+            Action::MoveRight,
+            Action::Sprint,
+            Action::Sneak,
+            Action::Dash,
+            Action::Interact,
+            Action::Inventory,
+            Action::CycleTarget,
+            Action::Fire,
+            Action::Reload,
+        ];
+        actions.extend((0..HOTBAR_SIZE as u8).map(Action::Hotbar));
+        actions.extend([
+            Action::Pause,
+            Action::Confirm,
+            Action::QuitToMenu,
+            Action::QuickSave,
+            Action::QuickLoad,
+            Action::CycleSaveSlot,
+            Action::NavigateUp,
+            Action::NavigateDown,
+            Action::NavigateLeft,
+            Action::NavigateRight,
+            Action::Insure,
+            Action::CycleFilter,
+            Action::SortStash,
+            Action::StartRaid,
+        ]);
+        actions
+    }
+
+    pub fn context(&self) -> ActionContext {
+        match self {
+            Action::Confirm
+            | Action::QuitToMenu
+            | Action::NavigateUp
+            | Action::NavigateDown
+            | Action::NavigateLeft
+            | Action::NavigateRight
+            | Action::Insure
+            | Action::CycleFilter
+            | Action::SortStash
+            | Action::StartRaid => ActionContext::Menu,
+            _ => ActionContext::Gameplay,
+        }
+    }
+
+    // The way in and out of the menus, so they can never be left without a binding
+    pub fn essential(&self) -> bool {
+        matches!(self, Action::Pause | Action::Confirm | Action::Inventory)
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Action::MoveUp => "Move Up".to_string(),
+            Action::MoveDown => "Move Down".to_string(),
+            Action::MoveLeft => "Move Left".to_string(),
+            Action::MoveRight => "Move Right".to_string(),
+            Action::CycleTarget => "Cycle Target".to_string(),
+            Action::Hotbar(index) => format!("Hotbar {}", index + 1),
+            Action::QuitToMenu => "Quit to Menu".to_string(),
+            Action::QuickSave => "Quick Save".to_string(),
+            Action::QuickLoad => "Quick Load".to_string(),
+            Action::CycleSaveSlot => "Cycle Save Slot".to_string(),
+            Action::NavigateUp => "Navigate Up".to_string(),
+            Action::NavigateDown => "Navigate Down".to_string(),
+            Action::NavigateLeft => "Navigate Left".to_string(),
+            Action::NavigateRight => "Navigate Right".to_string(),
+            Action::CycleFilter => "Cycle Filter".to_string(),
+            Action::SortStash => "Sort Stash".to_string(),
+            Action::StartRaid => "Start Raid".to_string(),
+            other => format!("{:?}", other),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::Gamepad(button) => format!("Pad {:?}", button),
+        }
+    }
+}
+
+// --- RESOURCES ---
+#[derive(Resource, Debug, Clone, Deserialize, Serialize)]
+pub struct InputMap {
+    pub bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let mut bindings = BTreeMap::new();
+        bindings.insert(Action::MoveUp, vec![Binding::Key(KeyCode::KeyW), Binding::Key(KeyCode::ArrowUp)]);
+        bindings.insert(Action::MoveDown, vec![Binding::Key(KeyCode::KeyS), Binding::Key(KeyCode::ArrowDown)]);
+        bindings.insert(Action::MoveLeft, vec![Binding::Key(KeyCode::KeyA), Binding::Key(KeyCode::ArrowLeft)]);
+        bindings.insert(Action::MoveRight, vec![Binding::Key(KeyCode::KeyD), Binding::Key(KeyCode::ArrowRight)]);
+        bindings.insert(Action::Sprint, vec![Binding::Key(KeyCode::ShiftLeft), Binding::Gamepad(GamepadButton::LeftThumb)]);
+        bindings.insert(Action::Sneak, vec![Binding::Key(KeyCode::KeyC), Binding::Key(KeyCode::ControlLeft), Binding::Gamepad(GamepadButton::RightThumb)]);
+        bindings.insert(Action::Dash, vec![Binding::Key(KeyCode::Space), Binding::Gamepad(GamepadButton::South)]);
+        bindings.insert(Action::Interact, vec![Binding::Key(KeyCode::KeyE), Binding::Gamepad(GamepadButton::North)]);
+        bindings.insert(Action::Inventory, vec![Binding::Key(KeyCode::KeyI), Binding::Gamepad(GamepadButton::Select)]);
+        bindings.insert(Action::CycleTarget, vec![Binding::Key(KeyCode::Tab), Binding::Gamepad(GamepadButton::RightTrigger)]);
+        bindings.insert(Action::Fire, vec![Binding::Mouse(MouseButton::Left), Binding::Gamepad(GamepadButton::RightTrigger2)]);
+        bindings.insert(Action::Reload, vec![Binding::Key(KeyCode::KeyR), Binding::Gamepad(GamepadButton::West)]);
+        let hotbar_keys = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4, KeyCode::Digit5];
+        for (index, key) in hotbar_keys.into_iter().enumerate().take(HOTBAR_SIZE) {
+            bindings.insert(Action::Hotbar(index as u8), vec![Binding::Key(key)]);
+        }
+        bindings.insert(Action::Pause, vec![Binding::Key(KeyCode::Escape), Binding::Gamepad(GamepadButton::Start)]);
+        bindings.insert(Action::Confirm, vec![Binding::Key(KeyCode::Enter), Binding::Gamepad(GamepadButton::South)]);
+        bindings.insert(Action::QuitToMenu, vec![Binding::Key(KeyCode::KeyQ), Binding::Gamepad(GamepadButton::East)]);
+        bindings.insert(Action::QuickSave, vec![Binding::Key(KeyCode::F5)]);
+        bindings.insert(Action::QuickLoad, vec![Binding::Key(KeyCode::F9)]);
+        bindings.insert(Action::CycleSaveSlot, vec![Binding::Key(KeyCode::F6)]);
+        bindings.insert(Action::NavigateUp, vec![Binding::Key(KeyCode::ArrowUp), Binding::Gamepad(GamepadButton::DPadUp)]);
+        bindings.insert(Action::NavigateDown, vec![Binding::Key(KeyCode::ArrowDown), Binding::Gamepad(GamepadButton::DPadDown)]);
+        bindings.insert(Action::NavigateLeft, vec![Binding::Key(KeyCode::ArrowLeft), Binding::Gamepad(GamepadButton::DPadLeft)]);
+        bindings.insert(Action::NavigateRight, vec![Binding::Key(KeyCode::ArrowRight), Binding::Gamepad(GamepadButton::DPadRight)]);
+        bindings.insert(Action::Insure, vec![Binding::Key(KeyCode::KeyG), Binding::Gamepad(GamepadButton::North)]);
+        bindings.insert(Action::CycleFilter, vec![Binding::Key(KeyCode::KeyF), Binding::Gamepad(GamepadButton::West)]);
+        bindings.insert(Action::SortStash, vec![Binding::Key(KeyCode::KeyO), Binding::Gamepad(GamepadButton::LeftTrigger)]);
+        bindings.insert(Action::StartRaid, vec![Binding::Key(KeyCode::Space), Binding::Gamepad(GamepadButton::Start)]);
+
+        Self { bindings }
+    }
+}
+
+impl InputMap {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    // Returns the other action in the same context already using this binding, if any.
+    pub fn conflict(&self, action: Action, binding: Binding) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(other, bindings)| {
+                **other != action && other.context() == action.context() && bindings.contains(&binding)
+            })
+            .map(|(other, _)| *other)
+    }
+
+    // Puts the binding in the given slot, appending when the slot does not exist yet.
+    pub fn bind(&mut self, action: Action, slot: usize, binding: Binding) -> Result<(), Action> {
+        if let Some(other) = self.conflict(action, binding) {
+            return Err(other);
+        }
+
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(|existing| *existing != binding);
+        if slot < bindings.len() {
+            bindings[slot] = binding;
+        }
+        else if bindings.len() < MAX_BINDINGS {
+            bindings.push(binding);
+        }
+        Ok(())
+    }
+
+    // Refused when it would take the last binding from an essential action.
+    pub fn unbind(&mut self, action: Action, slot: usize) -> bool {
+        let Some(bindings) = self.bindings.get_mut(&action) else {
+            return true;
+        };
+        if slot >= bindings.len() {
+            return true;
+        }
+        if action.essential() && bindings.len() == 1 {
+            return false;
+        }
+        bindings.remove(slot);
+        true
+    }
+
+    // Why the map cannot be played with, if it cannot.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(action) = Action::all().into_iter().find(|action| action.essential() && self.get(*action).is_empty()) {
+            return Err(format!("{} has no binding", action.label()));
+        }
+
+        for (action, bindings) in &self.bindings {
+            for binding in bindings {
+                if let Some(other) = self.conflict(*action, *binding) {
+                    return Err(format!("{} is bound to both {} and {}", binding.label(), action.label(), other.label()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self) {
+        let contents = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => contents,
+            Err(error) => {
+                println!("Failed to serialize bindings: {:?}", error);
+                return;
+            }
+        };
+
+        if let Err(error) = fs::create_dir_all(CONFIG_DIR).and_then(|_| fs::write(BINDINGS_FILE, contents)) {
+            println!("Failed to write {}: {:?}", BINDINGS_FILE, error);
+        }
+    }
+}
+
+// Per-frame view of the bindings, so gameplay systems ask about actions instead of keys.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+    move_axis: Vec2, // Left stick after the deadzone, zero when untouched
+    aim_axis: Vec2,  // Right stick after the deadzone, zero when untouched
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    pub fn move_axis(&self) -> Vec2 {
+        self.move_axis
+    }
+
+    pub fn aim_axis(&self) -> Vec2 {
+        self.aim_axis
+    }
+
+    // Sorted so recordings of the same input produce the same file
+    pub fn snapshot(&self) -> ActionSnapshot {
+        let sorted = |set: &HashSet<Action>| {
+            let mut actions: Vec<Action> = set.iter().copied().collect();
+            actions.sort();
+            actions
+        };
+
+        ActionSnapshot {
+            pressed: sorted(&self.pressed),
+            just_pressed: sorted(&self.just_pressed),
+            just_released: sorted(&self.just_released),
+            move_axis: self.move_axis,
+            aim_axis: self.aim_axis,
+        }
+    }
+
+    pub fn from_snapshot(snapshot: &ActionSnapshot) -> Self {
+        Self {
+            pressed: snapshot.pressed.iter().copied().collect(),
+            just_pressed: snapshot.just_pressed.iter().copied().collect(),
+            just_released: snapshot.just_released.iter().copied().collect(),
+            move_axis: snapshot.move_axis,
+            aim_axis: snapshot.aim_axis,
+        }
+    }
+}
+
+// ActionState as the fixed-step simulation sees it, rebuilt at the start of every tick.
+// A frame can run zero or several ticks, so presses wait here until the next tick and only that tick sees them.
+// Replays write their recorded ticks straight into it, so gameplay reads this and menus read ActionState.
+#[derive(Resource, Debug, Default)]
+pub struct TickActions {
+    pub state: ActionState,
+    pending_pressed: HashSet<Action>,
+    pending_released: HashSet<Action>,
+}
+
+// ActionState as it stood on one fixed tick, stored in replays
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ActionSnapshot {
+    pub pressed: Vec<Action>,
+    pub just_pressed: Vec<Action>,
+    pub just_released: Vec<Action>,
+    pub move_axis: Vec2,
+    pub aim_axis: Vec2,
+}
+
+// Radial deadzone, rescaled so the usable range still starts at zero.
+pub fn apply_deadzone(stick: Vec2, inner: f32, outer: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= inner {
+        return Vec2::ZERO;
+    }
+
+    let scaled = ((length - inner) / (outer - inner)).min(1.0);
+    stick / length * scaled
+}
+
+// --- CONFIG ---
+fn load_bindings() -> InputMap {
+    if !Path::new(BINDINGS_FILE).exists() {
+        return InputMap::default();
+    }
+
+    let loaded = fs::read_to_string(BINDINGS_FILE)
+        .map_err(|error| error.to_string())
+        .and_then(|contents| parse_bindings(&contents));
+
+    loaded.unwrap_or_else(|error| {
+        println!("Failed to load {}, using default bindings: {}", BINDINGS_FILE, error);
+        InputMap::default()
+    })
+}
+
+// Actions missing from an older config file keep their defaults.
+// A file that leaves the menus unreachable or binds one input twice is not used at all.
+fn parse_bindings(contents: &str) -> Result<InputMap, String> {
+    let loaded: InputMap = ron::from_str(contents).map_err(|error| error.to_string())?;
+    let mut input_map = InputMap::default();
+    input_map.bindings.extend(loaded.bindings);
+    input_map.validate()?;
+    Ok(input_map)
+}
+
+// --- SYSTEMS ---
+fn update_action_state(
+    input_map: Res<InputMap>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut action_state: ResMut<ActionState>,
+) {
+    action_state.pressed.clear();
+    action_state.just_pressed.clear();
+    action_state.just_released.clear();
+    action_state.move_axis = Vec2::ZERO;
+    action_state.aim_axis = Vec2::ZERO;
+
+    for (action, bindings) in &input_map.bindings {
+        for binding in bindings {
+            let (pressed, just_pressed, just_released) = match binding {
+                Binding::Key(key) => (
+                    keyboard_input.pressed(*key),
+                    keyboard_input.just_pressed(*key),
+                    keyboard_input.just_released(*key),
+                ),
+                Binding::Mouse(button) => (
+                    mouse_input.pressed(*button),
+                    mouse_input.just_pressed(*button),
+                    mouse_input.just_released(*button),
+                ),
+                Binding::Gamepad(button) => (
+                    gamepads.iter().any(|gamepad| gamepad.pressed(*button)),
+                    gamepads.iter().any(|gamepad| gamepad.just_pressed(*button)),
+                    gamepads.iter().any(|gamepad| gamepad.just_released(*button)),
+                ),
+            };
+
+            if pressed { action_state.pressed.insert(*action); }
+            if just_pressed { action_state.just_pressed.insert(*action); }
+            if just_released { action_state.just_released.insert(*action); }
+        }
+    }
+
+    // The first gamepad with a deflected stick wins
+    for gamepad in &gamepads {
+        let move_axis = apply_deadzone(gamepad.left_stick(), STICK_DEADZONE, STICK_OUTER);
+        if action_state.move_axis == Vec2::ZERO {
+            action_state.move_axis = move_axis;
+        }
+
+        let aim_axis = apply_deadzone(gamepad.right_stick(), STICK_DEADZONE, STICK_OUTER);
+        if action_state.aim_axis == Vec2::ZERO {
+            action_state.aim_axis = aim_axis;
+        }
+    }
+
+    // Letting go of one binding while another is still held does not release the action
+    let ActionState { pressed, just_released, .. } = &mut *action_state;
+    just_released.retain(|action| !pressed.contains(action));
+}
+
+fn collect_transitions(action_state: Res<ActionState>, mut tick: ResMut<TickActions>) {
+    tick.pending_pressed.extend(action_state.just_pressed.iter().copied());
+    tick.pending_released.extend(action_state.just_released.iter().copied());
+}
+
+// Presses made in a menu do not carry over into play
+fn clear_pending_transitions(mut tick: ResMut<TickActions>) {
+    tick.pending_pressed.clear();
+    tick.pending_released.clear();
+}
+
+fn begin_tick(action_state: Res<ActionState>, mut tick: ResMut<TickActions>) {
+    let TickActions { state, pending_pressed, pending_released } = &mut *tick;
+    *state = ActionState {
+        pressed: action_state.pressed.clone(),
+        just_pressed: std::mem::take(pending_pressed),
+        just_released: std::mem::take(pending_released),
+        move_axis: action_state.move_axis,
+        aim_axis: action_state.aim_axis,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_bindings_are_valid() {
+        assert_eq!(InputMap::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn essential_actions_keep_their_last_binding() {
+        let mut input_map = InputMap::default();
+        assert!(input_map.unbind(Action::Pause, 0));
+        assert!(!input_map.unbind(Action::Pause, 0));
+        assert_eq!(input_map.get(Action::Pause).len(), 1);
+
+        // Anything else can be cleared completely
+        assert!(input_map.unbind(Action::Reload, 0));
+        assert!(input_map.unbind(Action::Reload, 0));
+        assert!(input_map.get(Action::Reload).is_empty());
+    }
+
+    #[test]
+    fn broken_binding_files_are_not_used() {
+        let unbound = "(bindings: { Pause: [], Fire: [Mouse(Right)] })";
+        assert!(parse_bindings(unbound).unwrap_err().contains("Pause"));
+
+        let conflicting = "(bindings: { Reload: [Key(KeyE)] })"; // Interact keeps KeyE
+        assert!(parse_bindings(conflicting).is_err());
+
+        let fine = "(bindings: { Reload: [Key(KeyT)] })";
+        let input_map = parse_bindings(fine).unwrap();
+        assert_eq!(input_map.get(Action::Reload), &[Binding::Key(KeyCode::KeyT)]);
+        assert_eq!(input_map.get(Action::Pause), InputMap::default().get(Action::Pause));
+    }
+}
@@ -0,0 +1,235 @@
+use bevy::prelude::*;
+use crate::gameplay::input::input::{Action, Binding, InputMap, MAX_BINDINGS};
+use crate::gameplay::state::state::InGameState;
+
+const FONT_SIZE: f32 = 14.0;
+const BUTTON_COLOR: Color = Color::srgba(0.2, 0.2, 0.2, 0.9);
+const CAPTURE_COLOR: Color = Color::srgba(0.7, 0.5, 0.2, 0.9);
+
+// --- RESOURCES ---
+// Binding slot waiting for the next key or mouse button.
+#[derive(Resource, Default)]
+pub struct Rebinding {
+    pub capturing: Option<(Action, usize)>,
+    armed: bool, // Skips the frame of the click that started the capture
+    message: String,
+}
+
+// --- COMPONENTS ---
+#[derive(Component)]
+pub struct RebindButton {
+    action: Action,
+    slot: usize,
+}
+
+#[derive(Component)]
+pub struct RebindButtonText {
+    action: Action,
+    slot: usize,
+}
+
+#[derive(Component)]
+pub struct RebindStatusText;
+
+// --- CONDITIONS ---
+// Keys pressed while capturing belong to the rebinding, not to the menu.
+pub fn not_rebinding(rebinding: Res<Rebinding>) -> bool {
+    rebinding.capturing.is_none()
+}
+
+// --- SYSTEMS ---
+pub fn spawn_rebind_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    input_map: Res<InputMap>,
+) {
+    let font_handle: Handle<Font> = asset_server.load("fonts/ztn.otf");
+    let text_font = TextFont {
+        font: font_handle,
+        font_size: FONT_SIZE,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Percent(2.0),
+                top: Val::Percent(5.0),
+                width: Val::Percent(30.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(2.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.05, 0.05, 0.05, 0.9)),
+            GlobalZIndex(11),
+            DespawnOnExit(InGameState::Paused),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new("Controls"),
+                text_font.clone(),
+                TextColor(Color::WHITE),
+            ));
+            panel.spawn((
+                Text::new(""),
+                text_font.clone(),
+                TextColor(Color::srgb(0.9, 0.6, 0.3)),
+                RebindStatusText,
+            ));
+
+            for action in Action::all() {
+                panel
+                    .spawn(Node {
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            Node {
+                                width: Val::Percent(34.0),
+                                ..default()
+                            },
+                            Text::new(action.label()),
+                            text_font.clone(),
+                            TextColor(Color::WHITE),
+                        ));
+
+                        for slot in 0..MAX_BINDINGS {
+                            row.spawn((
+                                Button,
+                                Node {
+                                    width: Val::Percent(22.0),
+                                    padding: UiRect::horizontal(Val::Px(4.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(BUTTON_COLOR),
+                                RebindButton { action, slot },
+                                children![(
+                                    Text::new(binding_label(&input_map, action, slot)),
+                                    text_font.clone(),
+                                    TextColor(Color::WHITE),
+                                    RebindButtonText { action, slot },
+                                )],
+                            ));
+                        }
+                    });
+            }
+        });
+}
+
+pub fn click_binding(
+    button_query: Query<(&Interaction, &RebindButton), Changed<Interaction>>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for (interaction, button) in &button_query {
+        if *interaction == Interaction::Pressed {
+            rebinding.capturing = Some((button.action, button.slot));
+            rebinding.armed = false;
+            rebinding.message = format!(
+                "Press a key for {} (Esc cancels, Delete clears)",
+                button.action.label(),
+            );
+        }
+    }
+}
+
+pub fn capture_binding(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut rebinding: ResMut<Rebinding>,
+    mut input_map: ResMut<InputMap>,
+) {
+    let Some((action, slot)) = rebinding.capturing else {
+        return;
+    };
+
+    if !rebinding.armed {
+        rebinding.armed = true;
+        return;
+    }
+
+    let binding = if let Some(key) = keyboard_input.get_just_pressed().next() {
+        match key {
+            KeyCode::Escape => {
+                cancel(&mut rebinding);
+                return;
+            }
+            KeyCode::Delete => {
+                if input_map.unbind(action, slot) {
+                    input_map.save();
+                    cancel(&mut rebinding);
+                }
+                else {
+                    rebinding.message = format!("{} needs at least one binding", action.label());
+                }
+                return;
+            }
+            key => Binding::Key(*key),
+        }
+    }
+    else if let Some(button) = mouse_input.get_just_pressed().next() {
+        Binding::Mouse(*button)
+    }
+    else if let Some(button) = gamepads.iter().find_map(|gamepad| gamepad.get_just_pressed().next()) {
+        Binding::Gamepad(*button)
+    }
+    else {
+        return;
+    };
+
+    match input_map.bind(action, slot, binding) {
+        Ok(()) => {
+            input_map.save();
+            cancel(&mut rebinding);
+        }
+        Err(other) => {
+            rebinding.message = format!("{} is already bound to {}", binding.label(), other.label());
+        }
+    }
+}
+
+pub fn update_rebind_ui(
+    input_map: Res<InputMap>,
+    rebinding: Res<Rebinding>,
+    mut text_query: Query<(&RebindButtonText, &mut Text), Without<RebindStatusText>>,
+    mut status_query: Query<&mut Text, With<RebindStatusText>>,
+    mut button_query: Query<(&RebindButton, &mut BackgroundColor)>,
+) {
+    if !input_map.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+
+    for (button_text, mut text) in &mut text_query {
+        text.0 = binding_label(&input_map, button_text.action, button_text.slot);
+    }
+
+    for mut text in &mut status_query {
+        text.0 = rebinding.message.clone();
+    }
+
+    for (button, mut background) in &mut button_query {
+        let capturing = rebinding.capturing == Some((button.action, button.slot));
+        background.0 = if capturing { CAPTURE_COLOR } else { BUTTON_COLOR };
+    }
+}
+
+pub fn cancel_rebinding(mut rebinding: ResMut<Rebinding>) {
+    cancel(&mut rebinding);
+}
+
+fn cancel(rebinding: &mut Rebinding) {
+    rebinding.capturing = None;
+    rebinding.armed = false;
+    rebinding.message.clear();
+}
+
+fn binding_label(input_map: &InputMap, action: Action, slot: usize) -> String {
+    input_map
+        .get(action)
+        .get(slot)
+        .map(Binding::label)
+        .unwrap_or_else(|| "-".to_string())
+}
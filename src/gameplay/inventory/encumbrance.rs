@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+use crate::gameplay::equipment::equipment::Equipment;
+use crate::gameplay::inventory::inventory::Inventory;
+use crate::gameplay::item::items::ItemRegistry;
+use crate::gameplay::player::{player::Player, setup::Speed};
+use crate::gameplay::stats::modifiers::{Modifier, ModifierSource};
+
+const CARRY_CAPACITY: f32 = 20.0;
+const BURDENED_LOAD: f32 = 0.6; // Fraction of capacity where the player starts slowing down
+
+// --- ENUMS ---
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EncumbranceTier {
+    #[default]
+    Light,
+    Burdened,
+    Overloaded,
+}
+
+impl EncumbranceTier {
+    pub fn from_load(weight: f32, capacity: f32) -> Self {
+        if weight > capacity {
+            EncumbranceTier::Overloaded
+        }
+        else if weight > capacity * BURDENED_LOAD {
+            EncumbranceTier::Burdened
+        }
+        else {
+            EncumbranceTier::Light
+        }
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        match self {
+            EncumbranceTier::Light => 1.0,
+            EncumbranceTier::Burdened => 0.8,
+            EncumbranceTier::Overloaded => 0.5,
+        }
+    }
+
+    pub fn stamina_drain_multiplier(&self) -> f32 {
+        match self {
+            EncumbranceTier::Light => 1.0,
+            EncumbranceTier::Burdened => 1.5,
+            EncumbranceTier::Overloaded => 2.0,
+        }
+    }
+
+    pub fn can_sprint(&self) -> bool {
+        *self != EncumbranceTier::Overloaded
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            EncumbranceTier::Light => "Light",
+            EncumbranceTier::Burdened => "Burdened",
+            EncumbranceTier::Overloaded => "Overloaded",
+        }
+    }
+}
+
+// --- COMPONENTS ---
+#[derive(Component, Debug)]
+pub struct Encumbrance {
+    pub weight: f32,
+    pub capacity: f32,
+    pub tier: EncumbranceTier,
+}
+
+impl Default for Encumbrance {
+    fn default() -> Self {
+        Self {
+            weight: 0.0,
+            capacity: CARRY_CAPACITY,
+            tier: EncumbranceTier::Light,
+        }
+    }
+}
+
+// Marker for the carry weight readout in the inventory screen
+#[derive(Component)]
+pub struct EncumbranceText;
+
+// --- SYSTEMS ---
+pub fn update_encumbrance(
+    registry: Res<ItemRegistry>,
+    mut player_query: Query<
+        (&Inventory, &Equipment, &mut Encumbrance, &mut Speed),
+        (With<Player>, Or<(Changed<Inventory>, Changed<Equipment>)>),
+    >,
+) {
+    for (inventory, equipment, mut encumbrance, mut speed) in &mut player_query {
+        encumbrance.weight = inventory.weight(&registry) + equipment.weight(&registry);
+        encumbrance.tier = EncumbranceTier::from_load(encumbrance.weight, encumbrance.capacity);
+
+        let multiplier = encumbrance.tier.speed_multiplier();
+        if multiplier == 1.0 {
+            speed.modifiers.remove(ModifierSource::Encumbrance);
+        }
+        else {
+            speed.modifiers.set(ModifierSource::Encumbrance, Modifier::Multiplicative(multiplier));
+        }
+    }
+}
+
+pub fn update_encumbrance_text(
+    encumbrance: Single<&Encumbrance, (With<Player>, Changed<Encumbrance>)>,
+    mut text_query: Query<&mut Text, With<EncumbranceText>>,
+) {
+    for mut text in &mut text_query {
+        text.0 = format!(
+            "Weight: {:.1} / {:.1} ({})",
+            encumbrance.weight,
+            encumbrance.capacity,
+            encumbrance.tier.label(),
+        );
+    }
+}
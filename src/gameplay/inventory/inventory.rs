@@ -1,16 +1,33 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize}; 
-use crate::gameplay::inventory::ui::setup_ui;
-use crate::gameplay::inventory::ui::InventoryUi;
-use crate::gameplay::item::items::Item;
-use crate::gameplay::player::player::Player;
+use crate::gameplay::input::input::{Action, ActionState};
+use crate::gameplay::inventory::ui::{hide_inventory, setup_ui, show_inventory};
+use crate::gameplay::inventory::encumbrance::{update_encumbrance, update_encumbrance_text};
+use crate::gameplay::item::items::{Item, ItemDefinition, ItemRegistry};
+use crate::gameplay::stats::stats::StatsSystems;
+use crate::gameplay::state::state::{GameState, InGameState};
+
+// Cells an inventory's item shapes are packed into
+#[derive(Clone, Copy, Debug)]
+pub struct GridSize {
+    pub columns: i32,
+    pub rows: i32,
+}
+
+pub const PLAYER_GRID: GridSize = GridSize { columns: 6, rows: 8 };
 
 pub struct InventoryPlugin;
 impl Plugin for InventoryPlugin {
     fn build(&self, app: &mut App) {
         app 
-            .add_systems(Startup, setup_ui)
-            .add_systems(Update, activate_player_inventory);
+            .add_systems(OnEnter(GameState::InGame), setup_ui)
+            .add_systems(OnEnter(InGameState::Inventory), show_inventory)
+            .add_systems(OnExit(InGameState::Inventory), hide_inventory)
+            .add_systems(Update, (
+                activate_player_inventory,
+                update_encumbrance.before(StatsSystems),
+                update_encumbrance_text,
+            ).run_if(in_state(GameState::InGame)));
     }
 }
 
@@ -18,12 +35,11 @@ impl Plugin for InventoryPlugin {
 #[derive(Component, Default, Serialize, Deserialize, Clone, Debug)]
 pub struct Inventory {
     pub items: Vec<Item>, 
-    pub searching: bool
 }
 
 // TODO: support for quantity mutations as well. 
 impl Inventory {
-    fn add(&mut self, item: Item) {
+    pub fn add(&mut self, item: Item) {
         self.items.push(item);
     }
 
@@ -33,33 +49,85 @@ impl Inventory {
             self.items.remove(pos); 
         }
     }    
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.items.iter().any(|item| item.id == id)
+    }
+
+    // Removes and returns the first entry with a matching id. 
+    pub fn take(&mut self, id: &str) -> Option<Item> {
+        let pos = self.items.iter().position(|item| item.id == id)?;
+        Some(self.items.remove(pos))
+    }
+
+    // Total carried weight, items missing from the registry weigh nothing. 
+    pub fn weight(&self, registry: &ItemRegistry) -> f32 {
+        self.items
+            .iter()
+            .filter_map(|item| registry.get(&item.id).map(|def| def.weight * item.stack as f32))
+            .sum()
+    }
+
+    // Shape-grid rule shared by every storage. Items are packed first-fit from the top row in list order,
+    // turned only when rotatable and the upright shape fits nowhere. Items missing from the registry take no room
+    pub fn fits(&self, item: &Item, registry: &ItemRegistry, grid: GridSize) -> bool {
+        let mut cells = vec![false; (grid.columns * grid.rows) as usize];
+        self.items
+            .iter()
+            .chain(std::iter::once(item))
+            .all(|item| match registry.get(&item.id) {
+                Some(definition) => place_shape(&mut cells, grid, definition),
+                None => true,
+            })
+    }
 }
 
-#[derive(Component)]
-pub struct Searching; 
+fn place_shape(cells: &mut [bool], grid: GridSize, definition: &ItemDefinition) -> bool {
+    let mut shapes = vec![definition.shape.clone()];
+    if definition.rotatable {
+        shapes.push(definition.shape.rotate());
+    }
+
+    for shape in shapes {
+        for y in 0..=grid.rows - shape.height {
+            for x in 0..=grid.columns - shape.width {
+                let covered: Vec<usize> = (0..shape.height)
+                    .flat_map(|dy| (0..shape.width).map(move |dx| (dx, dy)))
+                    .filter(|(dx, dy)| shape.occupied(*dx, *dy))
+                    .map(|(dx, dy)| ((y + dy) * grid.columns + x + dx) as usize)
+                    .collect();
+
+                if covered.iter().all(|cell| !cells[*cell]) {
+                    for cell in covered {
+                        cells[cell] = true;
+                    }
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+// World storage such as crates and drawers. The id stays stable between runs so saves can find it again. 
+#[derive(Component, Debug)]
+pub struct Container {
+    pub id: String,
+}
 
 // --- SYSTEMS --- 
 fn activate_player_inventory(
-    keyboard_input: Res<ButtonInput<KeyCode>>, 
-    player: Single<Entity, With<Player>>,
-    mut commands: Commands,
-    mut inventory: Single<&mut Inventory, With<Player>>, 
-    mut visibility_query: Query<(&mut Visibility, &mut InventoryUi)>,
+    action_state: Res<ActionState>, 
+    state: Res<State<InGameState>>,
+    mut next_state: ResMut<NextState<InGameState>>,
 ) {
-    if !keyboard_input.just_pressed(KeyCode::KeyI) {
+    if !action_state.just_pressed(Action::Inventory) {
         return; 
     }
 
-    for (mut visibility, mut ui) in visibility_query.iter_mut() {
-        visibility.toggle_visible_hidden();
-        ui.activated = !ui.activated;
-        inventory.searching = !inventory.searching; 
-        
-        if inventory.searching {
-            commands.entity(*player).insert(Searching); 
-        }
-        else {
-            commands.entity(*player).remove::<Searching>(); 
-        }
+    match state.get() {
+        InGameState::Playing => next_state.set(InGameState::Inventory),
+        InGameState::Inventory => next_state.set(InGameState::Playing),
+        InGameState::Paused => {}
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,4 @@
+pub mod encumbrance;
 pub mod inventory; 
 pub mod interaction;
 pub mod ui; 
\ No newline at end of file
@@ -1,4 +1,9 @@
 use bevy::prelude::*; 
+use crate::gameplay::inventory::encumbrance::EncumbranceText;
+use crate::gameplay::state::state::GameState;
+
+const FONT_SIZE: f32 = 15.0;
+pub const INVENTORY_COLUMNS: u16 = 6;
 
 // --- COMPONENTS --- 
 #[derive(Component)]
@@ -7,10 +12,11 @@ pub struct InventoryUi {
 }
 
 #[derive(Component)]
-struct MainStorage;
+pub struct MainStorage;
 
 // --- SYSTEMS --- 
-pub fn setup_ui(mut commands: Commands) {
+pub fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font_handle: Handle<Font> = asset_server.load("fonts/ztn.otf");
     
     // Root node that contains all inventory parts 
     commands
@@ -27,6 +33,7 @@ pub fn setup_ui(mut commands: Commands) {
             BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.5)),
             Visibility::Hidden,
             InventoryUi {activated: false}, 
+            DespawnOnExit(GameState::InGame),
 
             // Other parts that make up inventory
             children![(
@@ -37,10 +44,43 @@ pub fn setup_ui(mut commands: Commands) {
                     height: percent(60),
                     align_self: AlignSelf::Start, 
                     justify_self: JustifySelf::Center,
+                    display: Display::Grid,
+                    grid_template_columns: vec![RepeatedGridTrack::flex(INVENTORY_COLUMNS, 1.0)],
+                    align_content: AlignContent::Start,
                     ..default()
                 },
                 BackgroundColor(Color::WHITE),
                 MainStorage,
+            ),
+            (
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(15.0),
+                    top: Val::Percent(64.0),
+                    ..default()
+                },
+                Text::new(""),
+                TextFont {
+                    font: font_handle.clone(),
+                    font_size: FONT_SIZE,
+                    ..default()
+                },
+                TextColor(Color::BLACK),
+                EncumbranceText,
             )]
     ));
-}
\ No newline at end of file
+}
+
+pub fn show_inventory(mut ui_query: Query<(&mut Visibility, &mut InventoryUi)>) {
+    for (mut visibility, mut ui) in &mut ui_query {
+        *visibility = Visibility::Visible;
+        ui.activated = true;
+    }
+}
+
+pub fn hide_inventory(mut ui_query: Query<(&mut Visibility, &mut InventoryUi)>) {
+    for (mut visibility, mut ui) in &mut ui_query {
+        *visibility = Visibility::Hidden;
+        ui.activated = false;
+    }
+}
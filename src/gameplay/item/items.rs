@@ -4,9 +4,14 @@ use std::{collections::HashMap};
 use serde::{Deserialize, Serialize}; 
 
 // --- PROJECT CRATES ---
+use crate::gameplay::combat::combat::WeaponStats;
+use crate::gameplay::equipment::equipment::EquipSlot;
 use crate::gameplay::player::setup::Layer;
 use crate::gameplay::player::setup::InteractionSensor;
 use crate::gameplay::cursor::cursor::CursorText;
+use crate::gameplay::stage::level::LevelEntity;
+use crate::gameplay::input::input::{Action, ActionState};
+use crate::gameplay::state::state::{GameState, InGameState};
 
 const LOOT_SIZE: Vec2 = Vec2::new(45.0, 45.0);
 
@@ -14,8 +19,9 @@ pub struct ItemPlugin;
 impl Plugin for ItemPlugin {
     fn build(&self, app: &mut App){
         app
-            .add_systems(Startup, (load_items, spawn_items))
-            .add_systems(Update, (load_loot_tooltip, cycle_loot_tooltip));
+            .add_systems(Startup, load_items)
+            .add_systems(OnEnter(GameState::InGame), reset_detected_loot)
+            .add_systems(Update, (load_loot_tooltip, cycle_loot_tooltip).run_if(in_state(InGameState::Playing)));
     }
 }
 
@@ -72,8 +78,8 @@ impl ItemShape {
     }
 }
  
-#[derive(Debug, Deserialize, Serialize)]
-enum ItemType {
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ItemType {
     Consumable, 
     Essential,
     Equipment,  
@@ -82,13 +88,19 @@ enum ItemType {
 // --- COMPONENTS --- 
 #[derive(Component, Debug, Deserialize, Serialize)]
 pub struct ItemDefinition {
-    id: String, 
-    item_type: ItemType,
+    pub id: String, 
+    pub item_type: ItemType,
     description: String, 
     max_stack: u8, // 0 - 255
-    shape: ItemShape,
-    rotatable: bool, 
+    pub shape: ItemShape,
+    pub rotatable: bool, 
     icon: String, 
+    #[serde(default)]
+    pub weight: f32, // Per unit, multiplied by stack size when carried
+    #[serde(default)]
+    pub slot: Option<EquipSlot>, // Only Equipment items have a slot
+    #[serde(default)]
+    pub weapon: Option<WeaponStats>, // Only firearms deal damage
 }
 
 // Contains necessary item info for lookups
@@ -96,6 +108,8 @@ pub struct ItemDefinition {
 pub struct Item {
     pub id: String, 
     pub stack: u8, 
+    #[serde(default)]
+    pub insured: bool, // Returned to the stash when the raid is lost
 }
 
 // Marker for floor items
@@ -108,7 +122,7 @@ pub struct PalpableLoot;
 
 // --- RESOURCES --- 
 #[derive(Default, Resource)]
-struct ItemRegistry {
+pub struct ItemRegistry {
     items: HashMap<String, ItemDefinition>, 
 }
 
@@ -117,8 +131,8 @@ impl ItemRegistry {
         self.items.insert(item.id.clone(), item); 
     }
 
-    fn get(&mut self, id: &str) -> Option<&ItemDefinition> {
-        self.get(id)
+    pub fn get(&self, id: &str) -> Option<&ItemDefinition> {
+        self.items.get(id)
     }
 }
 
@@ -147,33 +161,21 @@ fn load_items(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(registry);
 }
 
-fn spawn_items(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let item_id = "Bandage".to_string();
-for i in 0..8 {  
-    commands.spawn((
-        Item {id: item_id.clone(), stack: 1},
-        Loot, 
-        RigidBody::Static, 
-        Collider::circle(LOOT_SIZE.x / 2.0),
-        CollisionEventsEnabled, // Enable collision events for this entity
-        CollisionLayers::new(
-            [Layer::Item], // Does not collide with
-            [Layer::InteractionSensor], // collides with
-        ),
-        Sensor, // Sends collision events but allows other bodies to pass through them
-        Sprite {
-            image: asset_server.load("icons/prototype_loot.png"), 
-            custom_size: Some(LOOT_SIZE),
-            ..default()
-        }, 
-        Transform::from_xyz(150.0, 150.0, 1.0),
-    ))
-    .observe(loot_detection)
-    .observe(loot_undetected); 
+// Detections from a previous run point at despawned loot
+fn reset_detected_loot(mut detected: ResMut<DetectedLoot>) {
+    detected.items.clear();
+    detected.index = 0;
 }
 
-commands.spawn((
-        Item {id: item_id.clone(), stack: 1},
+// Spawns a floor item the player's InteractionSensor can detect
+pub fn spawn_loot(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    item: Item,
+    position: Vec2,
+) -> Entity {
+    commands.spawn((
+        item,
         Loot, 
         RigidBody::Static, 
         Collider::circle(LOOT_SIZE.x / 2.0),
@@ -188,10 +190,13 @@ commands.spawn((
             custom_size: Some(LOOT_SIZE),
             ..default()
         }, 
-        Transform::from_xyz(175.0, 150.0, 1.0),
+        Transform::from_xyz(position.x, position.y, 1.0),
+        LevelEntity, // Floor items stay behind when the player changes level
+        DespawnOnExit(GameState::InGame),
     ))
     .observe(loot_detection)
-    .observe(loot_undetected); 
+    .observe(loot_undetected)
+    .id()
 }
 
 // EVENT SYSTEM FOR LOOT DETECTION
@@ -261,13 +266,13 @@ fn load_loot_tooltip(
 
 fn cycle_loot_tooltip(
     mut detected: ResMut<DetectedLoot>, 
-    keyboard: Res<ButtonInput<KeyCode>>, 
+    action_state: Res<ActionState>, 
 ) {
     if detected.items.is_empty() {
         return;
     }
 
-    if keyboard.just_pressed(KeyCode::Tab) {
+    if action_state.just_pressed(Action::CycleTarget) {
         detected.index = (detected.index + 1) % detected.items.len();
         println!("Cycled! The current entity is: {:?}", detected.items.get(detected.index)); 
     }
@@ -1,10 +1,23 @@
 pub mod gameplay;
+pub mod combat;
 pub mod controller; 
 pub mod cursor; 
+pub mod equipment;
 pub mod gizmos;
+pub mod input;
 pub mod interactable;
 pub mod inventory;
 pub mod item;
+pub mod navigation;
+pub mod npc;
 pub mod player;
+pub mod raid;
+pub mod random;
+pub mod replay;
+pub mod save;
 pub mod stage;
+pub mod stash;
+pub mod state;
+pub mod stats;
+pub mod stealth;
 
@@ -0,0 +1,2 @@
+pub mod navigation;
+pub mod pathfinding;
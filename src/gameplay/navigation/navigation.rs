@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use crate::gameplay::navigation::pathfinding::{find_path, smooth_path, FlowField};
+use crate::gameplay::stage::level::{LevelDefinition, Obstacle};
+use crate::gameplay::stage::walls::{SolidTile, WallCollider};
+use crate::gameplay::state::state::{GameState, InGameState};
+
+const CLEARANCE: f32 = 20.0; // Colliders block every cell they come this close to
+const WAYPOINT_RADIUS: f32 = 30.0; // A waypoint counts as reached inside this distance
+const FLOW_GROUP: usize = 3; // Agents sharing a destination cell share one flow field from this many up
+const GOAL_SEARCH: u32 = 3; // Cells searched around a blocked destination for somewhere to stand
+
+pub struct NavigationPlugin;
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<NavGrid>()
+            .add_systems(Update, rebuild_nav_grid.run_if(in_state(GameState::InGame)))
+            .add_systems(OnExit(GameState::InGame), clear_nav_grid)
+            .add_systems(FixedUpdate, plan_paths.in_set(NavigationSystems).run_if(in_state(InGameState::Playing)));
+    }
+}
+
+// Path planning, so agents can set their destination before it
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NavigationSystems;
+
+// --- RESOURCES ---
+// The level's tile grid with every cell that cannot be walked through marked.
+// Version goes up on every rebuild so paths planned on an older grid get replanned.
+#[derive(Resource, Default)]
+pub struct NavGrid {
+    pub origin: Vec2, // World position of the bottom left corner
+    pub cell_size: f32,
+    pub size: UVec2,
+    pub blocked: Vec<bool>,
+    pub version: u32,
+}
+
+impl NavGrid {
+    pub fn index(&self, cell: UVec2) -> usize {
+        (cell.y * self.size.x + cell.x) as usize
+    }
+
+    pub fn contains(&self, cell: IVec2) -> bool {
+        cell.x >= 0 && cell.y >= 0 && (cell.x as u32) < self.size.x && (cell.y as u32) < self.size.y
+    }
+
+    pub fn walkable(&self, cell: UVec2) -> bool {
+        !self.blocked[self.index(cell)]
+    }
+
+    pub fn cell_at(&self, position: Vec2) -> Option<UVec2> {
+        let cell = ((position - self.origin) / self.cell_size).floor().as_ivec2();
+        self.contains(cell).then(|| cell.as_uvec2())
+    }
+
+    pub fn center(&self, cell: UVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * self.cell_size
+    }
+
+    // Closest walkable cell in growing rings around a blocked one
+    pub fn nearest_walkable(&self, cell: UVec2, radius: u32) -> Option<UVec2> {
+        if self.walkable(cell) {
+            return Some(cell);
+        }
+
+        let center = cell.as_ivec2();
+        (1..=radius as i32).find_map(|ring| {
+            (-ring..=ring)
+                .flat_map(|y| (-ring..=ring).map(move |x| IVec2::new(x, y)))
+                .filter(|offset| offset.x.abs() == ring || offset.y.abs() == ring)
+                .map(|offset| center + offset)
+                .filter(|near| self.contains(*near))
+                .map(|near| near.as_uvec2())
+                .find(|near| self.walkable(*near))
+        })
+    }
+}
+
+// --- COMPONENTS ---
+// Set a destination and walk toward steer() each tick, plan_paths does the rest
+#[derive(Component)]
+pub struct NavAgent {
+    pub radius: f32, // Smoothed paths keep this far from anything blocked
+    pub destination: Option<Vec2>,
+    pub path: Vec<Vec2>, // Waypoints still to walk, the last one is the destination
+    pub flow: bool, // Following a shared flow field rather than its own A* path
+    planned: Option<(UVec2, u32)>, // Destination cell and grid version the path was planned for
+}
+
+impl NavAgent {
+    pub fn new(radius: f32) -> Self {
+        Self { radius, destination: None, path: Vec::new(), flow: false, planned: None }
+    }
+
+    // Next point to walk toward. Until a path is planned that is the destination itself.
+    pub fn steer(&mut self, position: Vec2) -> Option<Vec2> {
+        let destination = self.destination?;
+        while self.path.len() > 1 && self.path[0].distance(position) <= WAYPOINT_RADIUS {
+            self.path.remove(0);
+        }
+        Some(self.path.first().copied().unwrap_or(destination))
+    }
+}
+
+// --- SYSTEMS ---
+// Emptied with the level, so a raid whose level fails to load cannot path through the last one
+fn clear_nav_grid(mut grid: ResMut<NavGrid>) {
+    let version = grid.version + 1;
+    *grid = NavGrid { version, ..default() };
+}
+
+// Rebuilt when the level loads, when solid tiles change (a door closing) and when obstacles come or go
+fn rebuild_nav_grid(
+    mut grid: ResMut<NavGrid>,
+    level: Option<Res<LevelDefinition>>,
+    tilemap_query: Query<&TileStorage>,
+    changed_tilemaps: Query<(), Changed<TileStorage>>,
+    solid_tiles: Query<(), With<SolidTile>>,
+    obstacle_query: Query<(&Collider, &Transform), (With<Obstacle>, Without<WallCollider>)>,
+    added_obstacles: Query<(), Added<Obstacle>>,
+    mut removed_obstacles: RemovedComponents<Obstacle>,
+) {
+    let Some(level) = level else {
+        return;
+    };
+    let removed = removed_obstacles.read().count() > 0;
+    if !level.is_changed() && changed_tilemaps.is_empty() && added_obstacles.is_empty() && !removed {
+        return;
+    }
+
+    let size = UVec2::new(level.size[0], level.size[1]);
+    let cell_size = level.tile_size;
+    let origin = -size.as_vec2() * cell_size * 0.5; // Tilemaps are centred on the world origin
+    let mut blocked = vec![false; (size.x * size.y) as usize];
+
+    // Tilemaps share the level's grid, so tile positions are cells
+    for storage in &tilemap_query {
+        for y in 0..storage.size.y.min(size.y) {
+            for x in 0..storage.size.x.min(size.x) {
+                if storage.get(&TilePos { x, y }).is_some_and(|tile| solid_tiles.contains(tile)) {
+                    blocked[(y * size.x + x) as usize] = true;
+                }
+            }
+        }
+    }
+
+    // Everything else solid, by its bounding box grown by the clearance
+    for (collider, transform) in &obstacle_query {
+        let aabb = collider.aabb(transform.translation.truncate(), Rotation::from(transform.rotation));
+        let min = ((aabb.min - CLEARANCE - origin) / cell_size).floor().as_ivec2().max(IVec2::ZERO);
+        let max = ((aabb.max + CLEARANCE - origin) / cell_size).floor().as_ivec2().min(size.as_ivec2() - 1);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                blocked[(y as u32 * size.x + x as u32) as usize] = true;
+            }
+        }
+    }
+
+    grid.origin = origin;
+    grid.cell_size = cell_size;
+    grid.size = size;
+    grid.blocked = blocked;
+    grid.version = grid.version.wrapping_add(1);
+}
+
+// Replans only when the destination moves to another cell or the grid changes
+fn plan_paths(
+    grid: Res<NavGrid>,
+    mut agent_query: Query<(&Transform, &mut NavAgent)>,
+    mut flow_fields: Local<HashMap<UVec2, FlowField>>,
+) {
+    let goal_of = |agent: &NavAgent| {
+        agent
+            .destination
+            .and_then(|destination| grid.cell_at(destination))
+            .and_then(|cell| grid.nearest_walkable(cell, GOAL_SEARCH))
+    };
+
+    let mut groups: HashMap<UVec2, usize> = HashMap::new();
+    for (_, agent) in &agent_query {
+        if let Some(goal) = goal_of(agent) {
+            *groups.entry(goal).or_default() += 1;
+        }
+    }
+    flow_fields.retain(|goal, field| field.version == grid.version && groups.get(goal).is_some_and(|&count| count >= FLOW_GROUP));
+
+    for (transform, mut agent) in &mut agent_query {
+        let Some(destination) = agent.destination else {
+            agent.path.clear();
+            agent.planned = None;
+            continue;
+        };
+
+        // Off the grid or nowhere to stand, so head straight there
+        let position = transform.translation.truncate();
+        let (Some(start), Some(goal)) = (grid.cell_at(position), goal_of(&agent)) else {
+            agent.path = vec![destination];
+            agent.planned = None;
+            continue;
+        };
+        if agent.planned == Some((goal, grid.version)) {
+            // Same cell, but the spot inside it may have moved
+            if let Some(last) = agent.path.last_mut() {
+                *last = destination;
+            }
+            continue;
+        }
+
+        agent.flow = groups.get(&goal).is_some_and(|&count| count >= FLOW_GROUP);
+        let cells = if agent.flow {
+            flow_fields
+                .entry(goal)
+                .or_insert_with(|| FlowField::new(&grid, goal))
+                .path_from(&grid, start)
+        }
+        else {
+            find_path(&grid, start, goal)
+        };
+
+        // The start cell is where the agent already is, and the goal cell gives way to the exact spot
+        agent.path = match cells {
+            Some(cells) => smooth_path(&grid, &cells, agent.radius)
+                .into_iter()
+                .skip(1)
+                .filter(|cell| Some(*cell) != grid.cell_at(destination))
+                .map(|cell| grid.center(cell))
+                .chain([destination])
+                .collect(),
+            None => Vec::new(), // No way through, steer() walks straight at it
+        };
+        agent.planned = Some((goal, grid.version));
+    }
+}
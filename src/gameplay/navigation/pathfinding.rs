@@ -0,0 +1,371 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use bevy::prelude::*;
+use crate::gameplay::navigation::navigation::NavGrid;
+
+// Step costs in tenths of a cell, so the open list can order on integers
+const STRAIGHT: u32 = 10;
+const DIAGONAL: u32 = 14;
+
+// Walkable cells around a cell and what it costs to step there.
+// Diagonals need both sides open, so paths never cut a wall corner.
+fn neighbours(grid: &NavGrid, cell: UVec2) -> impl Iterator<Item = (UVec2, u32)> + '_ {
+    let open = move |offset: IVec2| {
+        let next = cell.as_ivec2() + offset;
+        (grid.contains(next) && grid.walkable(next.as_uvec2())).then(|| next.as_uvec2())
+    };
+
+    [
+        IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y,
+        IVec2::new(1, 1), IVec2::new(1, -1), IVec2::new(-1, 1), IVec2::new(-1, -1),
+    ]
+    .into_iter()
+    .filter_map(move |offset| {
+        let next = open(offset)?;
+        if offset.x == 0 || offset.y == 0 {
+            return Some((next, STRAIGHT));
+        }
+        (open(IVec2::new(offset.x, 0)).is_some() && open(IVec2::new(0, offset.y)).is_some()).then_some((next, DIAGONAL))
+    })
+}
+
+// Octile distance, exact on an open grid with these step costs
+fn heuristic(from: UVec2, to: UVec2) -> u32 {
+    let delta = (from.as_ivec2() - to.as_ivec2()).abs().as_uvec2();
+    STRAIGHT * delta.max_element() + (DIAGONAL - STRAIGHT) * delta.min_element()
+}
+
+// A* from start to goal, both included. The start may be blocked, the agent is already there.
+pub fn find_path(grid: &NavGrid, start: UVec2, goal: UVec2) -> Option<Vec<UVec2>> {
+    let mut cost = vec![u32::MAX; grid.blocked.len()];
+    let mut came_from: Vec<Option<UVec2>> = vec![None; grid.blocked.len()];
+    let mut open = BinaryHeap::new();
+    cost[grid.index(start)] = 0;
+    // Ties break on position so the same grid always gives the same path
+    open.push(Reverse((heuristic(start, goal), start.x, start.y)));
+
+    while let Some(Reverse((estimate, x, y))) = open.pop() {
+        let cell = UVec2::new(x, y);
+        if cell == goal {
+            return Some(walk_back(grid, &came_from, goal));
+        }
+
+        let so_far = cost[grid.index(cell)];
+        if estimate > so_far + heuristic(cell, goal) {
+            continue; // Already reached more cheaply
+        }
+
+        for (next, step) in neighbours(grid, cell) {
+            let next_cost = so_far + step;
+            let index = grid.index(next);
+            if next_cost < cost[index] {
+                cost[index] = next_cost;
+                came_from[index] = Some(cell);
+                open.push(Reverse((next_cost + heuristic(next, goal), next.x, next.y)));
+            }
+        }
+    }
+
+    None
+}
+
+fn walk_back(grid: &NavGrid, came_from: &[Option<UVec2>], goal: UVec2) -> Vec<UVec2> {
+    let mut path = vec![goal];
+    while let Some(previous) = came_from[grid.index(*path.last().unwrap())] {
+        path.push(previous);
+    }
+    path.reverse();
+    path
+}
+
+// Drops every cell that can be skipped by walking straight past it.
+// Radius is the agent's in world units, so the straight lines keep it off wall corners.
+pub fn smooth_path(grid: &NavGrid, cells: &[UVec2], radius: f32) -> Vec<UVec2> {
+    let (Some(&first), Some(&last)) = (cells.first(), cells.last()) else {
+        return Vec::new();
+    };
+
+    let mut smoothed = vec![first];
+    let mut anchor = first;
+    for pair in cells.windows(2).skip(1) {
+        if !clear_line(grid, anchor, pair[1], radius) {
+            anchor = pair[0];
+            smoothed.push(anchor);
+        }
+    }
+    if last != first {
+        smoothed.push(last);
+    }
+    smoothed
+}
+
+// Sweeps a circle of the given radius between two cell centres and checks it touches no blocked cell.
+// The cell it starts from is ignored, and anything off the grid counts as blocked.
+pub fn clear_line(grid: &NavGrid, from: UVec2, to: UVec2, radius: f32) -> bool {
+    let radius = radius / grid.cell_size; // Everything below is in cells
+    let (start, end) = (from.as_vec2() + 0.5, to.as_vec2() + 0.5);
+    let min = (start.min(end) - radius).floor().as_ivec2();
+    let max = (start.max(end) + radius).floor().as_ivec2();
+
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            let cell = IVec2::new(x, y);
+            if cell == from.as_ivec2() || (grid.contains(cell) && grid.walkable(cell.as_uvec2())) {
+                continue;
+            }
+            if distance_to_cell(start, end, cell) <= radius {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// Closest a segment comes to a cell's square, zero when it passes through it
+fn distance_to_cell(start: Vec2, end: Vec2, cell: IVec2) -> f32 {
+    let (min, max) = (cell.as_vec2(), cell.as_vec2() + 1.0);
+    if crosses_box(start, end, min, max) {
+        return 0.0;
+    }
+
+    // Apart, the closest pair always has an end of the segment or a corner of the square in it
+    let to_box = |point: Vec2| point.distance(point.clamp(min, max));
+    let to_segment = |point: Vec2| {
+        let along = end - start;
+        let t = if along == Vec2::ZERO { 0.0 } else { ((point - start).dot(along) / along.length_squared()).clamp(0.0, 1.0) };
+        point.distance(start + along * t)
+    };
+    [min, max, Vec2::new(min.x, max.y), Vec2::new(max.x, min.y)]
+        .into_iter()
+        .map(to_segment)
+        .fold(to_box(start).min(to_box(end)), f32::min)
+}
+
+// Clips the segment against the box one axis at a time
+fn crosses_box(start: Vec2, end: Vec2, min: Vec2, max: Vec2) -> bool {
+    let along = end - start;
+    let (mut enter, mut exit) = (0.0f32, 1.0f32);
+    for axis in 0..2 {
+        if along[axis] == 0.0 {
+            if start[axis] < min[axis] || start[axis] > max[axis] {
+                return false;
+            }
+            continue;
+        }
+        let (a, b) = ((min[axis] - start[axis]) / along[axis], (max[axis] - start[axis]) / along[axis]);
+        enter = enter.max(a.min(b));
+        exit = exit.min(a.max(b));
+        if enter > exit {
+            return false;
+        }
+    }
+    true
+}
+
+// Cost to the goal from every cell, shared by a group walking to the same place.
+// Each agent only has to step downhill, so a crowd costs one search instead of one each.
+pub struct FlowField {
+    pub goal: UVec2,
+    pub version: u32, // NavGrid version it was built from
+    costs: Vec<u32>,
+}
+
+impl FlowField {
+    // Dijkstra outward from the goal
+    pub fn new(grid: &NavGrid, goal: UVec2) -> Self {
+        let mut costs = vec![u32::MAX; grid.blocked.len()];
+        let mut open = BinaryHeap::new();
+        costs[grid.index(goal)] = 0;
+        open.push(Reverse((0, goal.x, goal.y)));
+
+        while let Some(Reverse((cost, x, y))) = open.pop() {
+            let cell = UVec2::new(x, y);
+            if cost > costs[grid.index(cell)] {
+                continue;
+            }
+            for (next, step) in neighbours(grid, cell) {
+                let index = grid.index(next);
+                if cost + step < costs[index] {
+                    costs[index] = cost + step;
+                    open.push(Reverse((cost + step, next.x, next.y)));
+                }
+            }
+        }
+
+        Self { goal, version: grid.version, costs }
+    }
+
+    // Follows the cheapest neighbour from start until the goal, both included
+    pub fn path_from(&self, grid: &NavGrid, start: UVec2) -> Option<Vec<UVec2>> {
+        let mut path = vec![start];
+        let mut cell = start;
+        while cell != self.goal {
+            let (next, _) = neighbours(grid, cell).min_by_key(|(next, step)| self.costs[grid.index(*next)].saturating_add(*step))?;
+            if self.costs[grid.index(next)] >= self.costs[grid.index(cell)] {
+                return None; // Cut off from the goal
+            }
+            path.push(next);
+            cell = next;
+        }
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CELL: f32 = 100.0;
+    const RADIUS: f32 = 28.0;
+
+    // Rows are drawn top down, '#' is blocked
+    fn grid(rows: &[&str]) -> NavGrid {
+        let size = UVec2::new(rows[0].len() as u32, rows.len() as u32);
+        let blocked = rows.iter().rev().flat_map(|row| row.chars().map(|tile| tile == '#')).collect();
+        NavGrid { origin: Vec2::ZERO, cell_size: CELL, size, blocked, version: 0 }
+    }
+
+    fn close(grid: &mut NavGrid, cell: UVec2) {
+        let index = grid.index(cell);
+        grid.blocked[index] = true;
+        grid.version += 1;
+    }
+
+    // Closest the path's straight lines come to any blocked cell, in world units
+    fn tightest_squeeze(grid: &NavGrid, path: &[UVec2]) -> f32 {
+        let mut closest = f32::MAX;
+        for pair in path.windows(2) {
+            let (start, end) = (pair[0].as_vec2() + 0.5, pair[1].as_vec2() + 0.5);
+            for y in 0..grid.size.y {
+                for x in 0..grid.size.x {
+                    let cell = UVec2::new(x, y);
+                    if !grid.walkable(cell) {
+                        closest = closest.min(distance_to_cell(start, end, cell.as_ivec2()) * CELL);
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    #[test]
+    fn paths_go_around_walls() {
+        let grid = grid(&[
+            ".....",
+            ".###.",
+            ".....",
+        ]);
+        let path = find_path(&grid, UVec2::new(0, 1), UVec2::new(4, 1)).unwrap();
+
+        assert_eq!(path.first(), Some(&UVec2::new(0, 1)));
+        assert_eq!(path.last(), Some(&UVec2::new(4, 1)));
+        assert!(path.iter().all(|cell| grid.walkable(*cell)));
+        assert!(path.windows(2).all(|pair| (pair[0].as_ivec2() - pair[1].as_ivec2()).abs().max_element() == 1));
+    }
+
+    #[test]
+    fn a_blocked_goal_has_no_path() {
+        let grid = grid(&[
+            ".....",
+            "..#..",
+            ".....",
+        ]);
+        let goal = UVec2::new(2, 1);
+
+        assert_eq!(find_path(&grid, UVec2::new(0, 1), goal), None);
+        assert_eq!(FlowField::new(&grid, goal).path_from(&grid, UVec2::new(0, 1)), None);
+        // plan_paths stands next to it instead, and that can be reached
+        let stand = grid.nearest_walkable(goal, 3).unwrap();
+        assert!(find_path(&grid, UVec2::new(0, 1), stand).is_some());
+        assert!(FlowField::new(&grid, stand).path_from(&grid, UVec2::new(0, 1)).is_some());
+    }
+
+    #[test]
+    fn a_walled_off_goal_has_no_path() {
+        let grid = grid(&[
+            "..#..",
+            "..#..",
+            "..#..",
+        ]);
+        let (start, goal) = (UVec2::new(0, 1), UVec2::new(4, 1));
+
+        assert_eq!(find_path(&grid, start, goal), None);
+        assert_eq!(FlowField::new(&grid, goal).path_from(&grid, start), None);
+    }
+
+    #[test]
+    fn closing_a_door_replans_through_the_other() {
+        let mut grid = grid(&[
+            "..#..",
+            ".....",
+            "..#..",
+            "..#..",
+            ".....",
+            "..#..",
+        ]);
+        let (start, goal) = (UVec2::new(0, 3), UVec2::new(4, 3));
+        let (near_door, far_door) = (UVec2::new(2, 4), UVec2::new(2, 1));
+        let field = FlowField::new(&grid, goal);
+        assert!(find_path(&grid, start, goal).unwrap().contains(&near_door));
+        assert!(field.path_from(&grid, start).unwrap().contains(&near_door));
+
+        close(&mut grid, near_door);
+        assert_ne!(field.version, grid.version); // plan_paths drops the old field
+        let path = find_path(&grid, start, goal).unwrap();
+        assert!(path.contains(&far_door) && !path.contains(&near_door));
+        let path = FlowField::new(&grid, goal).path_from(&grid, start).unwrap();
+        assert!(path.contains(&far_door) && !path.contains(&near_door));
+
+        close(&mut grid, far_door);
+        assert_eq!(find_path(&grid, start, goal), None);
+        assert_eq!(FlowField::new(&grid, goal).path_from(&grid, start), None);
+    }
+
+    #[test]
+    fn smoothing_walks_straight_across_open_ground() {
+        let grid = grid(&[
+            ".....",
+            ".....",
+            ".....",
+        ]);
+        let path = find_path(&grid, UVec2::new(0, 0), UVec2::new(4, 2)).unwrap();
+
+        assert_eq!(smooth_path(&grid, &path, RADIUS), vec![UVec2::new(0, 0), UVec2::new(4, 2)]);
+        assert_eq!(smooth_path(&grid, &[UVec2::new(1, 1)], RADIUS), vec![UVec2::new(1, 1)]);
+        assert!(smooth_path(&grid, &[], RADIUS).is_empty());
+    }
+
+    #[test]
+    fn smoothing_keeps_the_agent_off_wall_corners() {
+        let grid = grid(&[
+            ".......",
+            "...#...",
+            "...#...",
+        ]);
+        let (start, goal) = (UVec2::new(0, 0), UVec2::new(6, 0));
+        let path = find_path(&grid, start, goal).unwrap();
+
+        // A bare line cuts right past the corner, too close for a body that wide
+        let bare = smooth_path(&grid, &path, 0.0);
+        assert!(tightest_squeeze(&grid, &bare) < RADIUS);
+
+        let smoothed = smooth_path(&grid, &path, RADIUS);
+        assert_eq!(smoothed.first(), Some(&start));
+        assert_eq!(smoothed.last(), Some(&goal));
+        assert!(tightest_squeeze(&grid, &smoothed) > RADIUS);
+    }
+
+    #[test]
+    fn smoothing_still_fits_through_a_one_cell_door() {
+        let grid = grid(&[
+            "..#..",
+            ".....",
+            "..#..",
+        ]);
+        let path = find_path(&grid, UVec2::new(0, 0), UVec2::new(4, 2)).unwrap();
+        let smoothed = smooth_path(&grid, &path, RADIUS);
+
+        assert!(smoothed.len() > 2);
+        assert!(tightest_squeeze(&grid, &smoothed) > RADIUS);
+    }
+}
@@ -0,0 +1,258 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use crate::gameplay::combat::combat::hitscan;
+use crate::gameplay::item::items::ItemRegistry;
+use crate::gameplay::navigation::navigation::NavAgent;
+use crate::gameplay::npc::npc::Npc;
+use crate::gameplay::npc::perception::Perception;
+use crate::gameplay::player::aim::VisibilityCone;
+use crate::gameplay::player::health::{Damage, Health};
+use crate::gameplay::player::setup::Speed;
+use crate::gameplay::stats::modifiers::{Modifier, ModifierSource};
+use crate::gameplay::stealth::stealth::Noise;
+
+pub const ALARM_DISTANCE: f32 = 300.0; // How far back along a shot an NPC goes looking for the shooter
+const ARRIVE_DISTANCE: f32 = 20.0;
+const BASE_VELOCITY: f32 = 250.0; // Same scale as the player controller, a Speed of 1.0 walks at this
+const ACCELERATION: f32 = 1200.0;
+const FLEE_DISTANCE: f32 = 400.0; // How far ahead a fleeing NPC aims to run
+const BACK_OFF: f32 = 0.6; // Inside this fraction of the preferred range a fighter steps back
+
+// --- ENUMS ---
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AiState {
+    #[default]
+    Idle,
+    Patrol,
+    Investigate,
+    Combat,
+    Flee,
+}
+
+// --- COMPONENTS ---
+#[derive(Component, Debug)]
+pub struct Brain {
+    pub state: AiState,
+    pub timer: f32, // Seconds in the current state, in Combat and Flee since the target was last seen
+    pub target: Option<Entity>,
+    pub last_known: Option<Vec2>, // Where the target was seen or glimpsed, or something was heard
+    pub home: Vec2,
+    pub patrol: Vec<Vec2>,
+    pub waypoint: usize,
+    pub fire_cooldown: f32,
+    pub stun: f32, // Seconds without control after being hit
+}
+
+impl Brain {
+    pub fn new(home: Vec2, patrol: Vec<Vec2>) -> Self {
+        Self {
+            state: AiState::Idle,
+            timer: 0.0,
+            target: None,
+            last_known: None,
+            home,
+            patrol,
+            waypoint: 0,
+            fire_cooldown: 0.0,
+            stun: 0.0,
+        }
+    }
+
+    // Only a real change resets the timer, so re-entering the same state keeps counting
+    pub fn enter(&mut self, state: AiState) {
+        if self.state != state {
+            self.state = state;
+            self.timer = 0.0;
+        }
+    }
+
+    // Back to the route after a fight or a false alarm
+    fn settle(&mut self) {
+        self.target = None;
+        self.last_known = None;
+        let next = if self.patrol.is_empty() { AiState::Idle } else { AiState::Patrol };
+        self.enter(next);
+    }
+}
+
+// --- SYSTEMS ---
+pub fn think(
+    mut npc_query: Query<(&Npc, &Health, &Perception, &Transform, &mut Brain)>,
+    time: Res<Time>,
+) {
+    let delta_time = time.delta_secs();
+    for (npc, health, perception, transform, mut brain) in &mut npc_query {
+        let archetype = &npc.archetype;
+        let position = transform.translation.truncate();
+        brain.timer += delta_time;
+        brain.fire_cooldown = (brain.fire_cooldown - delta_time).max(0.0);
+        brain.stun = (brain.stun - delta_time).max(0.0);
+
+        if let Some((target, seen_at)) = perception.seen {
+            let afraid = archetype.weapon.is_none() || health.current <= health.max * archetype.flee_health;
+            brain.target = Some(target);
+            brain.last_known = Some(seen_at);
+            brain.enter(if afraid { AiState::Flee } else { AiState::Combat });
+            brain.timer = 0.0;
+            continue;
+        }
+
+        match brain.state {
+            AiState::Combat => {
+                // Footsteps keep a lost target on the map
+                if let Some(heard) = perception.heard {
+                    brain.last_known = Some(heard);
+                }
+                if brain.timer >= archetype.give_up_time {
+                    brain.target = None;
+                    brain.enter(AiState::Investigate);
+                }
+            }
+            AiState::Flee => {
+                if brain.timer >= archetype.give_up_time {
+                    brain.settle();
+                }
+            }
+            // A glimpse is a better lead than a noise
+            _ if perception.glimpsed.or(perception.heard).is_some() => {
+                brain.last_known = perception.glimpsed.or(perception.heard);
+                brain.enter(AiState::Investigate);
+                brain.timer = 0.0;
+            }
+            AiState::Investigate => {
+                // Only time spent standing at the spot counts towards giving up
+                let arrived = brain.last_known.is_none_or(|spot| spot.distance(position) <= ARRIVE_DISTANCE);
+                if !arrived {
+                    brain.timer = 0.0;
+                }
+                else if brain.timer >= archetype.investigate_time {
+                    brain.settle();
+                }
+            }
+            AiState::Patrol => {
+                let Some(&waypoint) = brain.patrol.get(brain.waypoint) else {
+                    brain.enter(AiState::Idle);
+                    continue;
+                };
+                if waypoint.distance(position) <= ARRIVE_DISTANCE {
+                    brain.waypoint = (brain.waypoint + 1) % brain.patrol.len();
+                    brain.enter(AiState::Idle);
+                }
+            }
+            AiState::Idle => {
+                if !brain.patrol.is_empty() && brain.timer >= archetype.idle_time {
+                    brain.enter(AiState::Patrol);
+                }
+            }
+        }
+    }
+}
+
+// Steers along a path to whatever the current state wants and fires when a target is in sight
+pub fn act(
+    mut npc_query: Query<(
+        Entity,
+        &Npc,
+        &Perception,
+        &Transform,
+        &mut Brain,
+        &mut Speed,
+        &mut LinearVelocity,
+        &mut VisibilityCone,
+        &mut NavAgent,
+    )>,
+    health_query: Query<(), With<Health>>,
+    registry: Res<ItemRegistry>,
+    spatial_query: SpatialQuery,
+    mut damage_writer: MessageWriter<Damage>,
+    mut noise_writer: MessageWriter<Noise>,
+    time: Res<Time>,
+) {
+    let delta_time = time.delta_secs();
+    for (entity, npc, perception, transform, mut brain, mut speed, mut linear_velocity, mut cone, mut agent) in &mut npc_query {
+        if brain.stun > 0.0 {
+            continue;
+        }
+
+        let archetype = &npc.archetype;
+        let position = transform.translation.truncate();
+        let threat = brain.last_known.unwrap_or(brain.home);
+        let (goal, running) = match brain.state {
+            AiState::Idle => {
+                let away = brain.home.distance(position) > ARRIVE_DISTANCE && brain.patrol.is_empty();
+                (away.then_some(brain.home), false)
+            }
+            AiState::Patrol => (brain.patrol.get(brain.waypoint).copied(), false),
+            AiState::Investigate => (brain.last_known, false),
+            AiState::Combat => {
+                let distance = threat.distance(position);
+                let goal = if distance > archetype.preferred_range {
+                    Some(threat)
+                }
+                else if distance < archetype.preferred_range * BACK_OFF {
+                    Some(position + (position - threat).normalize_or_zero() * archetype.preferred_range)
+                }
+                else {
+                    None
+                };
+                (goal, true)
+            }
+            AiState::Flee => (Some(position + (position - threat).normalize_or_zero() * FLEE_DISTANCE), true),
+        };
+
+        if running {
+            speed.modifiers.set(ModifierSource::Sprint, Modifier::Multiplicative(archetype.run_speed));
+        }
+        else if speed.modifiers.contains(ModifierSource::Sprint) {
+            speed.modifiers.remove(ModifierSource::Sprint);
+        }
+
+        // The path is planned after this, so a new goal is walked straight at for one tick
+        agent.destination = goal.filter(|goal| goal.distance(position) > ARRIVE_DISTANCE);
+        let direction = agent
+            .steer(position)
+            .map(|point| (point - position).normalize_or_zero())
+            .unwrap_or(Vec2::ZERO);
+        let target_velocity = direction * speed.modifiers.apply(speed.base) * BASE_VELOCITY;
+        linear_velocity.0 = linear_velocity.0.move_towards(target_velocity, ACCELERATION * delta_time);
+
+        // Fighters keep their eyes on the target, everyone else looks where they walk
+        let facing = match brain.state {
+            AiState::Combat => threat - position,
+            _ => direction,
+        };
+        if facing != Vec2::ZERO {
+            cone.direction = facing.normalize();
+        }
+
+        if brain.state != AiState::Combat || brain.fire_cooldown > 0.0 {
+            continue;
+        }
+        let Some((_, seen_at)) = perception.seen else {
+            continue;
+        };
+        let Some(weapon) = archetype
+            .weapon
+            .as_deref()
+            .and_then(|id| registry.get(id))
+            .and_then(|definition| definition.weapon)
+        else {
+            continue;
+        };
+        let Ok(aim) = Dir2::new(seen_at - position) else {
+            continue;
+        };
+        if seen_at.distance(position) > weapon.range {
+            continue;
+        }
+
+        brain.fire_cooldown = archetype.fire_interval;
+        noise_writer.write(Noise { source: entity, position, radius: weapon.noise });
+        let Some(hit) = hitscan(&spatial_query, entity, position, aim, &weapon) else {
+            continue;
+        };
+        if health_query.contains(hit) {
+            damage_writer.write(weapon.damage_along(hit, aim));
+        }
+    }
+}
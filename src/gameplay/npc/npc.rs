@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use avian2d::{math::*, prelude::*};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::gameplay::combat::combat::fire_weapon;
+use crate::gameplay::item::items::{spawn_loot, Item};
+use crate::gameplay::navigation::navigation::{NavAgent, NavigationSystems};
+use crate::gameplay::npc::ai::*;
+use crate::gameplay::npc::perception::{perceive, Perception};
+use crate::gameplay::player::aim::VisibilityCone;
+use crate::gameplay::player::health::{apply_damage, Damage, Health};
+use crate::gameplay::player::setup::{Layer, Speed};
+use crate::gameplay::state::state::{GameState, InGameState};
+use crate::gameplay::stats::modifiers::ModifierStack;
+
+const ARCHETYPE_FILE: &str = "assets/npcs/archetypes.ron";
+const NPC_RADIUS: f32 = 28.0;
+const DAMPING: f32 = 5.0;
+const LOOT_SPREAD: f32 = 25.0; // Dropped items fan out this far so they can be told apart
+
+pub struct NpcPlugin;
+impl Plugin for NpcPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Startup, load_archetypes)
+            .add_systems(Update, spawn_npcs.run_if(in_state(GameState::InGame)))
+            // Same fixed rate as the player, so replays see the same outlaws do the same things
+            .add_systems(FixedUpdate, (
+                perceive,
+                react_to_damage,
+                think,
+                act,
+                npc_death.after(apply_damage),
+            ).chain().after(fire_weapon).before(NavigationSystems).run_if(in_state(InGameState::Playing)));
+    }
+}
+
+// --- DATA ---
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ArchetypeDatabase {
+    pub archetypes: Vec<NpcArchetype>,
+}
+
+// Tuning for one kind of NPC, see assets/npcs/archetypes.ron
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NpcArchetype {
+    pub id: String,
+    pub health: f32,
+    pub speed: f32, // Walking Speed.base, 1.0 matches the player
+    pub run_speed: f32, // Multiplier while fighting or fleeing
+    pub vision_range: f32,
+    pub vision_angle: f32, // Whole cone in degrees
+    pub hearing: f32, // Multiplier on the radius of every Noise
+    pub weapon: Option<String>, // Item id, unarmed NPCs never fight
+    pub fire_interval: f32,
+    pub preferred_range: f32, // Distance kept from the target in combat
+    pub flee_health: f32, // Fraction of max health at or under which they run
+    pub idle_time: f32, // Seconds stood still before patrolling again
+    pub investigate_time: f32, // Seconds spent looking around where something was heard
+    pub give_up_time: f32, // Seconds without sight of the target before combat or flight ends
+    pub color: [f32; 3],
+    #[serde(default)]
+    pub loot: Vec<Item>, // Dropped on death
+}
+
+// --- RESOURCES ---
+#[derive(Default, Resource)]
+pub struct NpcArchetypes {
+    archetypes: HashMap<String, NpcArchetype>,
+}
+
+impl NpcArchetypes {
+    pub fn get(&self, id: &str) -> Option<&NpcArchetype> {
+        self.archetypes.get(id)
+    }
+}
+
+// --- COMPONENTS ---
+// Placed by the level, turned into an Npc once the archetype is looked up
+#[derive(Component)]
+pub struct NpcSpawn {
+    pub archetype: String,
+    pub patrol: Vec<Vec2>,
+}
+
+// Each NPC keeps its own copy so the AI never needs the registry
+#[derive(Component)]
+pub struct Npc {
+    pub archetype: NpcArchetype,
+}
+
+// --- SYSTEMS ---
+fn load_archetypes(mut commands: Commands) {
+    let ron_str = std::fs::read_to_string(ARCHETYPE_FILE)
+        .expect("Failed to read archetypes.ron");
+    let database: ArchetypeDatabase = ron::from_str(&ron_str).expect("Failed to parse archetypes.ron");
+
+    let archetypes = database
+        .archetypes
+        .into_iter()
+        .map(|archetype| (archetype.id.clone(), archetype))
+        .collect();
+    commands.insert_resource(NpcArchetypes { archetypes });
+}
+
+fn spawn_npcs(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    archetypes: Res<NpcArchetypes>,
+    spawn_query: Query<(Entity, &NpcSpawn, &Transform), Added<NpcSpawn>>,
+) {
+    for (entity, spawn, transform) in &spawn_query {
+        let Some(archetype) = archetypes.get(&spawn.archetype) else {
+            println!("Unknown NPC archetype {}", spawn.archetype);
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let [red, green, blue] = archetype.color;
+        commands
+            .entity(entity)
+            .insert((
+                Npc { archetype: archetype.clone() },
+                Brain::new(transform.translation.truncate(), spawn.patrol.clone()),
+                Perception::default(),
+                NavAgent::new(NPC_RADIUS),
+                Health {
+                    base: archetype.health,
+                    max: archetype.health,
+                    current: archetype.health,
+                    modifiers: ModifierStack::default(),
+                },
+                Speed { base: archetype.speed, current: archetype.speed, modifiers: ModifierStack::default() },
+                VisibilityCone {
+                    range: archetype.vision_range,
+                    angle: archetype.vision_angle.to_radians(),
+                    direction: Vec2::NEG_Y,
+                },
+                Mesh2d(meshes.add(Circle::new(NPC_RADIUS))),
+                MeshMaterial2d(materials.add(Color::srgb(red, green, blue))),
+                RigidBody::Dynamic,
+                Collider::circle(NPC_RADIUS as Scalar),
+                LockedAxes::ROTATION_LOCKED,
+                LinearDamping(DAMPING), // Bleeds off knockback while stunned
+                // Default membership keeps them solid to the player's sweep and to hitscans
+                CollisionLayers::new([Layer::Default, Layer::Npc], LayerMask::ALL),
+            ))
+            .remove::<NpcSpawn>();
+    }
+}
+
+// Knockback like the player's, and a hit from nowhere is worth looking into
+fn react_to_damage(
+    mut damage_reader: MessageReader<Damage>,
+    mut npc_query: Query<(&Transform, &mut LinearVelocity, &mut Brain, &mut Perception), With<Npc>>,
+) {
+    for damage in damage_reader.read() {
+        let Ok((transform, mut linear_velocity, mut brain, mut perception)) = npc_query.get_mut(damage.target) else {
+            continue;
+        };
+
+        linear_velocity.0 += damage.knockback;
+        brain.stun = brain.stun.max(damage.stun);
+        if perception.heard.is_none() {
+            let back_along_shot = -damage.knockback.normalize_or_zero() * ALARM_DISTANCE;
+            perception.heard = Some(transform.translation.truncate() + back_along_shot);
+        }
+    }
+}
+
+fn npc_death(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    npc_query: Query<(Entity, &Npc, &Health, &Transform)>,
+) {
+    for (entity, npc, health, transform) in &npc_query {
+        if health.current > 0.0 {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        for (index, item) in npc.archetype.loot.iter().enumerate() {
+            let offset = Vec2::from_angle(index as f32 * 2.4) * LOOT_SPREAD * index.min(1) as f32;
+            spawn_loot(&mut commands, &asset_server, item.clone(), position + offset);
+        }
+        commands.entity(entity).despawn();
+    }
+}
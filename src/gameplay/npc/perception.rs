@@ -0,0 +1,110 @@
+use avian2d::{math::*, prelude::*};
+use bevy::prelude::*;
+use crate::gameplay::npc::ai::{AiState, Brain};
+use crate::gameplay::npc::npc::Npc;
+use crate::gameplay::player::aim::VisibilityCone;
+use crate::gameplay::player::player::Player;
+use crate::gameplay::player::setup::Layer;
+use crate::gameplay::stage::level::Obstacle;
+use crate::gameplay::stealth::stealth::{Footprint, Noise};
+
+const SPOT_TIME: f32 = 2.0; // Seconds to spot an upright target at the very edge of vision
+const CLOSE_BONUS: f32 = 4.0; // Extra fill rate at point blank, fading out toward the edge
+const SUSPICION_DECAY: f32 = 0.25; // Per second while nothing is in sight
+pub const SUSPICIOUS: f32 = 0.5; // Above this the NPC walks over to take a look
+
+// --- COMPONENTS ---
+// What the NPC noticed this tick, written by perceive and read by think.
+// Suspicion runs from 0.0 to 1.0, and a target is only seen once it is full.
+#[derive(Component, Debug, Default)]
+pub struct Perception {
+    pub suspicion: f32,
+    pub seen: Option<(Entity, Vec2)>,
+    pub glimpsed: Option<Vec2>, // Something in sight that is not yet worth a fight
+    pub heard: Option<Vec2>,
+}
+
+// --- SYSTEMS ---
+pub fn perceive(
+    mut noise_reader: MessageReader<Noise>,
+    spatial_query: SpatialQuery,
+    obstacle_query: Query<(), With<Obstacle>>,
+    mut npc_query: Query<(Entity, &Npc, &Brain, &Transform, &VisibilityCone, &mut Perception)>,
+    target_query: Query<(Entity, &Transform, &Footprint), (With<Player>, Without<Npc>)>,
+    time: Res<Time>,
+) {
+    let delta_time = time.delta_secs();
+    let noises: Vec<Noise> = noise_reader.read().copied().collect();
+    for (entity, npc, brain, transform, cone, mut perception) in &mut npc_query {
+        let position = transform.translation.truncate();
+
+        // The target that would raise suspicion fastest, if any is in view
+        let sighting = target_query
+            .iter()
+            .map(|(target, target_transform, footprint)| (target, target_transform.translation.truncate(), footprint.scale))
+            .filter(|(_, target_position, _)| in_cone(position, cone, *target_position))
+            .filter(|(_, target_position, _)| line_of_sight(&spatial_query, &obstacle_query, position, *target_position))
+            .map(|(target, target_position, scale)| {
+                let rate = suspicion_rate(position.distance(target_position), cone.range) * scale;
+                (target, target_position, rate)
+            })
+            .max_by(|a, b| a.2.total_cmp(&b.2));
+
+        perception.suspicion = match sighting {
+            // Already fighting or running, there is no second look needed
+            Some(_) if matches!(brain.state, AiState::Combat | AiState::Flee) => 1.0,
+            Some((_, _, rate)) => (perception.suspicion + rate * delta_time).min(1.0),
+            None => (perception.suspicion - SUSPICION_DECAY * delta_time).max(0.0),
+        };
+        let suspicion = perception.suspicion;
+        perception.seen = sighting
+            .filter(|_| suspicion >= 1.0)
+            .map(|(target, target_position, _)| (target, target_position));
+        perception.glimpsed = sighting
+            .filter(|_| suspicion >= SUSPICIOUS)
+            .map(|(_, target_position, _)| target_position);
+
+        // The nearest thing in earshot is the one worth walking to
+        perception.heard = noises
+            .iter()
+            .filter(|noise| noise.source != entity)
+            .filter(|noise| noise.position.distance(position) <= noise.radius * npc.archetype.hearing)
+            .min_by(|a, b| a.position.distance(position).total_cmp(&b.position.distance(position)))
+            .map(|noise| noise.position);
+    }
+}
+
+// Inside the cone's range and within half its angle either side of where it faces
+pub fn in_cone(position: Vec2, cone: &VisibilityCone, point: Vec2) -> bool {
+    let offset = point - position;
+    let distance = offset.length();
+    if distance > cone.range {
+        return false;
+    }
+    distance <= f32::EPSILON || cone.direction.angle_to(offset).abs() <= cone.angle * 0.5
+}
+
+// Only Obstacles block sight, so crates, loot and other bodies can be seen past
+pub fn line_of_sight(
+    spatial_query: &SpatialQuery,
+    obstacle_query: &Query<(), With<Obstacle>>,
+    from: Vec2,
+    to: Vec2,
+) -> bool {
+    let Ok(direction) = Dir2::new(to - from) else {
+        return true;
+    };
+
+    let filter = SpatialQueryFilter::from_mask([Layer::Default]);
+    spatial_query
+        .cast_ray_predicate(from, direction, from.distance(to) as Scalar, true, &filter, &|entity| {
+            obstacle_query.contains(entity)
+        })
+        .is_none()
+}
+
+// Suspicion per second for an upright target, faster the closer it stands
+fn suspicion_rate(distance: f32, range: f32) -> f32 {
+    let closeness = 1.0 - (distance / range).clamp(0.0, 1.0);
+    (1.0 + CLOSE_BONUS * closeness) / SPOT_TIME
+}
@@ -1,5 +1,8 @@
 use super::{player::Player};
-use bevy::prelude::*;
+use bevy::{prelude::*, window::CursorMoved};
+use crate::gameplay::input::input::ActionState;
+
+const STICK_AIM_DISTANCE: f32 = 200.0;
 
 #[derive(Resource)]
 pub struct MousePos {
@@ -7,6 +10,20 @@ pub struct MousePos {
     // To get mouse screen position use: <Window> -> cursor_position(). 
 }
 
+// Point the player turns toward. Follows the mouse until the right stick is used and back again when the cursor moves.
+#[derive(Resource)]
+pub struct AimTarget {
+    pub position: Vec2,
+    pub source: AimSource,
+    pub stick_direction: Vec2, // Last deflected right stick direction, kept when the stick is released
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AimSource {
+    Mouse,
+    Gamepad,
+}
+
 #[derive(Component)]
 pub struct VisibilityCone {
     pub range: f32,
@@ -32,13 +49,36 @@ pub fn get_mouse_position(
     }
 }
 
+pub fn update_aim_target(
+    mut cursor_moved: MessageReader<CursorMoved>,
+    action_state: Res<ActionState>,
+    mouse_pos: Res<MousePos>,
+    player: Single<&Transform, With<Player>>,
+    mut aim_target: ResMut<AimTarget>,
+) {
+    if cursor_moved.read().last().is_some() {
+        aim_target.source = AimSource::Mouse;
+    }
+
+    let stick = action_state.aim_axis();
+    if stick != Vec2::ZERO {
+        aim_target.source = AimSource::Gamepad;
+        aim_target.stick_direction = stick.normalize();
+    }
+
+    aim_target.position = match aim_target.source {
+        AimSource::Mouse => mouse_pos.position,
+        AimSource::Gamepad => player.translation.truncate() + aim_target.stick_direction * STICK_AIM_DISTANCE,
+    };
+}
+
 pub fn rotate_aim(
     mut player_query: Query<(&mut Transform, &mut VisibilityCone), With<Player>>,
-    mouse_pos: Res<MousePos>,
+    aim_target: Res<AimTarget>,
     time: Res<Time>,
 ) {
     let rotation_speed = f32::to_radians(360.0);
-    let aim_translation = mouse_pos.position.xy();
+    let aim_translation = aim_target.position;
     for (mut player_transform, mut cone) in &mut player_query {
         let player_forward = (player_transform.rotation * Vec3::Y).xy();
         let to_aim = (aim_translation - player_transform.translation.xy()).normalize();
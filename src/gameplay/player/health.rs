@@ -1,9 +1,14 @@
 use bevy::prelude::*; 
+use crate::gameplay::player::player::Player;
+use crate::gameplay::stats::modifiers::ModifierStack;
+use crate::gameplay::raid::raid::{EndRaid, RaidOutcome};
 
 #[derive(Component)]
 pub struct Health {
+    pub base: f32, // Max health before modifiers
     pub max: f32,
     pub current: f32, 
+    pub modifiers: ModifierStack,
 }
 
 #[derive(Component)]
@@ -11,6 +16,22 @@ pub struct HealthBar {
     pub entity: Entity,
 }
 
+// Damage is ignored while this is present, removed once the window runs out. 
+#[derive(Component)]
+pub struct Invulnerable {
+    pub remaining: f32,
+}
+
+// --- MESSAGES ---
+// Knockback is a velocity change along the hit direction, stun is in seconds. 
+#[derive(Message)]
+pub struct Damage {
+    pub target: Entity,
+    pub amount: f32,
+    pub knockback: Vec2,
+    pub stun: f32,
+}
+
 pub fn update_health(
     health_query: Query<&Health>, 
     mut bar_query: Query<(&HealthBar, &mut Node), With<HealthBar>>,
@@ -20,4 +41,37 @@ pub fn update_health(
             node.width = Val::Percent(health.current / health.max * 100.0); 
         }
     }
-}
\ No newline at end of file
+}
+
+pub fn apply_damage(
+    mut damage_reader: MessageReader<Damage>,
+    mut health_query: Query<&mut Health, Without<Invulnerable>>,
+) {
+    for damage in damage_reader.read() {
+        if let Ok(mut health) = health_query.get_mut(damage.target) {
+            health.current = (health.current - damage.amount).max(0.0);
+        }
+    }
+}
+
+pub fn tick_invulnerability(
+    mut commands: Commands,
+    mut invulnerable_query: Query<(Entity, &mut Invulnerable)>,
+    time: Res<Time>,
+) {
+    for (entity, mut invulnerable) in &mut invulnerable_query {
+        invulnerable.remaining -= time.delta_secs();
+        if invulnerable.remaining <= 0.0 {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+pub fn check_death(
+    health: Single<&Health, With<Player>>,
+    mut end_writer: MessageWriter<EndRaid>,
+) {
+    if health.current <= 0.0 {
+        end_writer.write(EndRaid(RaidOutcome::Died));
+    }
+}
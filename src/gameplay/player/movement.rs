@@ -1,7 +1,9 @@
 use avian2d::{math::*, prelude::*}; 
 use bevy::{ecs::entity::EntityHashSet, prelude::*};
 use serde::{Deserialize, Serialize};
-use crate::gameplay::inventory::inventory::Inventory;
+use crate::gameplay::inventory::encumbrance::Encumbrance;
+use crate::gameplay::stealth::stealth::Footprint;
+use crate::gameplay::stats::modifiers::{Modifier, ModifierSource};
 use super::{
     player::{Player, PlayerStatus, Status},
     setup::Speed,
@@ -9,44 +11,69 @@ use super::{
 };
 
 const RUN_SPEED: f32 = 1.5;
-const STAMINA_DRAIN: f32 = 0.25; 
+const SNEAK_SPEED: f32 = 0.5;
+const SNEAK_FOOTPRINT: f32 = 0.5; // Fraction of the normal detection footprint while sneaking
+const SPRINT_STAMINA_COST: f32 = 15.0; // Per second of sprinting
 const EMPTY: f32 = 0.0;
 
 // --- COMPONENTS --- 
 #[derive(Component)]
 pub struct DebugText; 
 
-pub fn run(
-    player_query: Single<(&mut Stamina, &mut Speed, &mut PlayerStatus), With<Player>>,
-    inventory: Single<&Inventory>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-) {
-    let (mut stamina, mut speed, mut player) = player_query.into_inner(); 
-    if player.condition != Status::Normal || inventory.searching {
-        return; 
+// Applies the sprint bonus and pays its stamina cost for this step. 
+// Returns false when the player cannot sprint, the bonus is removed in that case. 
+pub fn sprint(
+    stamina: &mut Stamina,
+    speed: &mut Speed,
+    status: &mut PlayerStatus,
+    encumbrance: &Encumbrance,
+    delta_time: f32,
+) -> bool {
+    if status.condition != Status::Normal || !encumbrance.tier.can_sprint() || stamina.current <= EMPTY {
+        stop_sprint(speed);
+        return false;
     }
 
-    if keyboard_input.pressed(KeyCode::ShiftLeft) {
-        speed.current = RUN_SPEED; 
-        if stamina.current > EMPTY { stamina.current -= STAMINA_DRAIN; }
-        else { 
-            stamina.current = EMPTY;
-            player.condition = Status::Exhausted;
-            speed.current = speed.base; 
-        }
+    speed.modifiers.set(ModifierSource::Sprint, Modifier::Multiplicative(RUN_SPEED));
+    stamina.current -= SPRINT_STAMINA_COST * encumbrance.tier.stamina_drain_multiplier() * delta_time;
+    if stamina.current <= EMPTY {
+        stamina.current = EMPTY;
+        status.condition = Status::Exhausted;
+        stop_sprint(speed);
+        return false;
     }
-    else if keyboard_input.just_released(KeyCode::ShiftLeft) {
-        speed.current = speed.base;
+
+    true
+}
+
+pub fn stop_sprint(speed: &mut Speed) {
+    if speed.modifiers.contains(ModifierSource::Sprint) {
+        speed.modifiers.remove(ModifierSource::Sprint);
     }
 }
 
-pub fn prevent_movement (
-    inventory: Single<&Inventory>,  
-    // mut velocity: Single<&mut Velocity, With<Player>>, 
-) {
-    if !inventory.searching {
-        return; 
+// Sneaking trades speed for a smaller footprint. Sprinting is dropped while sneaking. 
+pub fn sneak(speed: &mut Speed, footprint: &mut Footprint) {
+    stop_sprint(speed);
+    speed.modifiers.set(ModifierSource::Sneak, Modifier::Multiplicative(SNEAK_SPEED));
+    footprint.scale = SNEAK_FOOTPRINT;
+}
+
+pub fn stop_sneak(speed: &mut Speed, footprint: &mut Footprint) {
+    if speed.modifiers.contains(ModifierSource::Sneak) {
+        speed.modifiers.remove(ModifierSource::Sneak);
     }
+    footprint.scale = 1.0;
+}
+
+// Leaving Playing (inventory, pause) must not leave the sprint or sneak modifiers behind. 
+pub fn stop_running(player: Single<(&mut Speed, &mut Footprint), With<Player>>) {
+    let (mut speed, mut footprint) = player.into_inner();
+    stop_sprint(&mut speed);
+    stop_sneak(&mut speed, &mut footprint);
+}
 
-    // velocity.linvel = Vec3::ZERO; 
-}
\ No newline at end of file
+// The player stands still while rummaging through the inventory. 
+pub fn prevent_movement(mut velocity: Single<&mut LinearVelocity, With<Player>>) {
+    velocity.0 = Vector::ZERO;
+}
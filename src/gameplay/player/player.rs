@@ -1,15 +1,20 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use crate::gameplay::item::items::DetectedLoot;
-use crate::gameplay::player::aim::MousePos;
+use crate::gameplay::player::aim::{AimSource, AimTarget, MousePos};
+use crate::gameplay::player::aim::update_aim_target;
 use crate::gameplay::player::setup::spawn_player;
 use crate::gameplay::player::aim::get_mouse_position;
 use crate::gameplay::player::aim::rotate_aim;
-use crate::gameplay::player::movement::run;
 use crate::gameplay::player::stamina::update_stamina;
 use crate::gameplay::player::health::update_health;
 use crate::gameplay::player::stamina::restore_stamina;
-use crate::gameplay::player::movement::prevent_movement;
+use crate::gameplay::player::movement::{prevent_movement, stop_running};
+use crate::gameplay::player::health::{apply_damage, check_death, tick_invulnerability, Damage};
 use crate::gameplay::player::stamina::Stamina;
+use crate::gameplay::replay::replay::replaying;
+use crate::gameplay::stage::level::{LevelDefinition, LevelSystems};
+use crate::gameplay::state::state::{GameState, InGameState};
 
 const ZERO: f32 = 0.0;
 const SEC: f32 = 1.0; 
@@ -23,27 +28,47 @@ impl Plugin for PlayerPlugin {
             .insert_resource(MousePos {
                 position: Vec2::new(0.0, 0.0)
             })
+            .insert_resource(AimTarget {
+                position: Vec2::ZERO,
+                source: AimSource::Mouse,
+                stick_direction: Vec2::Y,
+            })
             .insert_resource(DetectedLoot {
                 items: Vec::new(),
                 index: 0,
             })
+            .add_message::<Damage>()
             .add_systems(
-            Startup,
-            spawn_player,
+            OnEnter(GameState::InGame),
+            spawn_player.after(LevelSystems).run_if(resource_exists::<LevelDefinition>),
             )
+            .add_systems(OnEnter(InGameState::Inventory), prevent_movement)
+            .add_systems(OnExit(InGameState::Playing), stop_running)
             .add_systems(
             Update,
             (
                 get_mouse_position,
-                rotate_aim.after(get_mouse_position),
-                run,
-                // apply_velocity,
                 update_stamina,
                 update_health,
+                check_death,
+            ).run_if(in_state(GameState::InGame)))
+            .add_systems(
+            Update,
+            (
+                update_aim_target.after(get_mouse_position).run_if(not(replaying)),
+                // apply_velocity,
+            ).run_if(in_state(InGameState::Playing)))
+            // Stamina and status tick at the same fixed rate as the controller that spends stamina.
+            // Turning does too, so the cone the outlaws react to does not depend on the frame rate.
+            .add_systems(
+            FixedUpdate,
+            (
+                rotate_aim,
                 restore_stamina,
                 exhaust,
-                prevent_movement.after(run),
-            ));
+                tick_invulnerability,
+                apply_damage,
+            ).chain().run_if(in_state(InGameState::Playing)));
     }
 }
 
@@ -58,7 +83,7 @@ pub struct PlayerStatus {
     pub duration: f32, // Timer for status-effects
 }
 
-#[derive(Component, PartialEq)]
+#[derive(Component, Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub enum Status {
     Normal,
     Exhausted, 
@@ -82,6 +107,65 @@ fn exhaust (
         
     }
     else {
-        status.duration += SEC * timer.delta_secs();
+        status.duration += SEC * timer.delta_secs(); // Time<Fixed> in FixedUpdate
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use bevy::time::TimeUpdateStrategy;
+    use super::*;
+    use crate::gameplay::controller::plugin::tests::{run_for, SIMULATED_SECONDS};
+    use crate::gameplay::player::setup::Speed;
+    use crate::gameplay::stats::modifiers::ModifierStack;
+
+    // Past the end of an exhaustion, a whole number of frames at every rate below and clear of a tick boundary
+    const EXHAUSTED_SECONDS: f64 = 19.0 / 3.0;
+
+    // Stamina and status on their own, ticked the way PlayerPlugin ticks them
+    fn simulate(fps: f64, status: Status, stamina: f32, seconds: f64) -> (Status, f32, f32) {
+        let mut app = App::new();
+        app
+            .add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / fps)))
+            .add_systems(FixedUpdate, (restore_stamina, exhaust).chain());
+        let player = app.world_mut().spawn((
+            Player,
+            PlayerStatus { condition: status, duration: ZERO },
+            Stamina { base: 100.0, max: 100.0, current: stamina, modifiers: ModifierStack::default() },
+            Speed { base: 1.0, current: 1.0, modifiers: ModifierStack::default() },
+        )).id();
+        run_for(&mut app, seconds);
+
+        let world = app.world();
+        let status = world.get::<PlayerStatus>(player).unwrap();
+        (status.condition, status.duration, world.get::<Stamina>(player).unwrap().current)
+    }
+
+    #[test]
+    fn stamina_regenerates_the_same_at_any_frame_rate() {
+        let result = simulate(60.0, Status::Normal, 50.0, SIMULATED_SECONDS);
+        assert!(result.2 > 50.0, "stamina never regenerated");
+
+        for fps in [30.0, 144.0] {
+            assert_eq!(simulate(fps, Status::Normal, 50.0, SIMULATED_SECONDS), result, "{} FPS diverged from 60 FPS", fps);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn exhaustion_lasts_the_same_at_any_frame_rate() {
+        // Still exhausted part way through, with nothing regenerated
+        let waiting = simulate(60.0, Status::Exhausted, ZERO, SIMULATED_SECONDS);
+        assert_eq!((waiting.0, waiting.2), (Status::Exhausted, ZERO));
+
+        // Over by the end, and the stamina regenerated since then pins down the tick it ended on
+        let recovered = simulate(60.0, Status::Exhausted, ZERO, EXHAUSTED_SECONDS);
+        assert_eq!(recovered.0, Status::Normal);
+        assert!(recovered.2 > RECOVERY, "stamina never regenerated after the exhaustion");
+
+        for fps in [30.0, 144.0] {
+            assert_eq!(simulate(fps, Status::Exhausted, ZERO, SIMULATED_SECONDS), waiting, "{} FPS diverged from 60 FPS", fps);
+            assert_eq!(simulate(fps, Status::Exhausted, ZERO, EXHAUSTED_SECONDS), recovered, "{} FPS diverged from 60 FPS", fps);
+        }
+    }
+}
@@ -6,26 +6,38 @@ use bevy::{
 };
 use crate::gameplay::controller::plugin::PlayerControllerBundle;
 use crate::gameplay::player::{aim::*, health::*, movement::*, player::{Player, PlayerStatus, Status}, stamina::*};
-use crate::gameplay::inventory::inventory::Inventory;
+use crate::gameplay::equipment::{equipment::Equipment, hotbar::{ActiveItem, Hotbar}};
+use crate::gameplay::inventory::{encumbrance::Encumbrance, inventory::Inventory};
 use crate::gameplay::item::items::DetectedLoot;
+use crate::gameplay::stash::stash::Loadout;
+use crate::gameplay::stats::modifiers::ModifierStack;
+use crate::gameplay::stage::level::LevelDefinition;
+use crate::gameplay::state::state::GameState;
+use crate::gameplay::stealth::stealth::{Footprint, NoiseEmitter};
 
 const WALK_SPEED: f32 = 1.0;
 const ZERO: f32 = 0.0; 
 
 // Sizing and Physics Variables
-const ACCLERATION: f32 = 500.0; 
+const ACCLERATION: f32 = 1500.0; 
 const DAMPING: f32 = 5.0; 
-const JUMP_IMPULSE: f32 = 400.0; 
+const DASH_IMPULSE: f32 = 900.0; 
 const RADIUS: f32 = 30.0;
 
 // --- BUNDLES --- 
 #[derive(Bundle)]
 struct PlayerBundle {
     player: Player,
+    active_item: ActiveItem,
+    encumbrance: Encumbrance,
+    equipment: Equipment,
+    footprint: Footprint,
     health: Health,
+    hotbar: Hotbar,
     inventory: Inventory,  
     mesh: Mesh2d,
     material: MeshMaterial2d<ColorMaterial>,
+    noise: NoiseEmitter,
     speed: Speed,
     stamina: Stamina,
     status: PlayerStatus,
@@ -41,6 +53,8 @@ pub enum Layer {
     InteractionSensor, 
     Item, 
     Player,
+    Trigger, // Level exits
+    Npc,
 }
 
 // --- COMPONENTS --- 
@@ -50,33 +64,47 @@ pub struct InteractionSensor;
 #[derive(Component)]
 pub struct Speed {
     pub base: f32,
-    pub current: f32, 
+    pub current: f32, // Final value after modifiers, recomputed by StatsPlugin
+    pub modifiers: ModifierStack,
 }
 
 pub fn spawn_player(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    level: Res<LevelDefinition>,
+    mut loadout: ResMut<Loadout>,
 ) {
     let shape = Circle::new(RADIUS);
+    let spawn = level.player_spawn();
     let player = commands
         .spawn((
             PlayerBundle {
                 player: Player,
+                active_item: ActiveItem::default(),
+                encumbrance: Encumbrance::default(),
+                equipment: Equipment::default(),
+                footprint: Footprint::default(),
                 health: Health {
+                    base: 100.0,
                     max: 100.0,
                     current: 100.0,
+                    modifiers: ModifierStack::default(),
                 },
-                inventory: Inventory { items: Vec::new(), searching: false }, 
+                hotbar: Hotbar::default(),
+                inventory: Inventory { items: std::mem::take(&mut loadout.inventory.items) }, // Taken into the raid
                 mesh: Mesh2d(meshes.add(shape)),
                 material: MeshMaterial2d(materials.add(Color::from(RED))),
-                speed: Speed {base: WALK_SPEED, current: WALK_SPEED},
+                noise: NoiseEmitter::default(),
+                speed: Speed {base: WALK_SPEED, current: WALK_SPEED, modifiers: ModifierStack::default()},
                 stamina: Stamina {
+                    base: 100.0,
                     max: 100.0,
                     current: 100.0,
+                    modifiers: ModifierStack::default(),
                 },
                 status: PlayerStatus { condition: Status::Normal, duration: ZERO},
-                transform: Transform::from_xyz(100.0, 0.0, 2.0),
+                transform: Transform::from_xyz(spawn.x, spawn.y, 2.0),
                 visibility: VisibilityCone {
                     range: 1000.0,
                     angle: 90.0_f32.to_radians(),
@@ -86,12 +114,10 @@ pub fn spawn_player(
             PlayerControllerBundle::new(Collider::circle(RADIUS)).with_movement(
                 ACCLERATION,
                 DAMPING,
-                JUMP_IMPULSE,
+                DASH_IMPULSE,
             ),
-            ColliderDensity(2.0),
-            Friction::ZERO.with_combine_rule(CoefficientCombine::Min),
-            GravityScale(0.0),
-            Restitution::ZERO.with_combine_rule(CoefficientCombine::Min),
+            ColliderDensity(2.0), // Mass used when shoving dynamic bodies
+            DespawnOnExit(GameState::InGame),
         ))
         .with_children(|parent| {
             parent.spawn((
@@ -119,6 +145,7 @@ pub fn spawn_player(
             },
             BackgroundColor(Color::srgba(0.3, 0.3, 0.3, 0.9)),
             BorderColor::all(Color::BLACK),
+            DespawnOnExit(GameState::InGame),
         ))
         .with_children(|parent| {
             parent.spawn((
@@ -144,6 +171,7 @@ pub fn spawn_player(
             },
             BackgroundColor(Color::srgba(0.3, 0.3, 0.3, 0.9)),
             BorderColor::all(Color::BLACK),
+            DespawnOnExit(GameState::InGame),
         ))
         .with_children(|parent| {
             parent.spawn((
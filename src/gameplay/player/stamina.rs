@@ -3,14 +3,16 @@ use crate::gameplay::player::{
     player::{Player, PlayerStatus, Status},
     setup::Speed,
 };
+use crate::gameplay::stats::modifiers::{ModifierSource, ModifierStack};
 
-const REGEN: f32 = 2.0;
-const WALK_SPEED: f32 = 85.0;
+const REGEN: f32 = 2.0; // Per second while not sprinting
 
 #[derive(Component)]
 pub struct Stamina{
+    pub base: f32, // Max stamina before modifiers
     pub max: f32,
     pub current: f32, 
+    pub modifiers: ModifierStack,
 }
 
 #[derive(Component)]
@@ -38,9 +40,10 @@ pub fn restore_stamina(
         return; 
     }
 
-    if stamina.current >= 100.0 && speed.current != WALK_SPEED {
+    // Sprinting pays its own cost in the controller, regen waits until the bonus is gone
+    if stamina.current >= stamina.max || speed.modifiers.contains(ModifierSource::Sprint) {
         return; 
     }
 
-    stamina.current += REGEN * time.delta_secs(); 
+    stamina.current = (stamina.current + REGEN * time.delta_secs()).min(stamina.max); 
 }
\ No newline at end of file
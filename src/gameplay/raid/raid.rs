@@ -0,0 +1,249 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use crate::gameplay::equipment::{equipment::Equipment, ui::InventoryFocus};
+use crate::gameplay::input::input::{Action, ActionState};
+use crate::gameplay::inventory::inventory::Inventory;
+use crate::gameplay::item::items::{Item, ItemRegistry};
+use crate::gameplay::player::player::Player;
+use crate::gameplay::stash::stash::{Stash, STASH_GRID};
+use crate::gameplay::state::state::{GameState, InGameState};
+
+const RAID_DURATION: f32 = 900.0; // Seconds before the town swallows whoever is still inside
+pub const EXTRACTION_TIME: f32 = 8.0; // Default seconds to stand in an extraction zone
+const INSURED_LIMIT: usize = 3; // Items that can be insured at once
+const FONT_SIZE: f32 = 18.0;
+
+pub struct RaidPlugin;
+impl Plugin for RaidPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Raid>()
+            .add_message::<EndRaid>()
+            .add_systems(OnEnter(GameState::InGame), (start_raid, spawn_raid_ui))
+            // Same fixed rate as movement, so replays extract on the same tick
+            .add_systems(FixedUpdate, (
+                tick_raid,
+                tick_extraction,
+            ).run_if(in_state(InGameState::Playing)))
+            .add_systems(Update, toggle_insurance.run_if(in_state(InGameState::Inventory)))
+            .add_systems(Update, (
+                update_raid_ui,
+                end_raid,
+            ).run_if(in_state(GameState::InGame)));
+    }
+}
+
+// --- ENUMS ---
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RaidOutcome {
+    Extracted,
+    Died,
+    OutOfTime, // Counts as dying, only insured items come back
+    Abandoned, // Quit from the pause menu, also counts as dying
+}
+
+// --- MESSAGES ---
+#[derive(Message)]
+pub struct EndRaid(pub RaidOutcome);
+
+// --- COMPONENTS ---
+// Standing inside for `seconds` ends the raid with everything carried
+#[derive(Component)]
+pub struct ExtractionZone {
+    pub seconds: f32,
+}
+
+#[derive(Component)]
+struct RaidText;
+
+// --- RESOURCES ---
+#[derive(Resource, Debug)]
+pub struct Raid {
+    pub remaining: f32,
+    pub extracting: Option<f32>, // Seconds the zone the player stands in requires
+    pub extraction: f32, // Seconds spent in it so far
+    pub outcome: Option<RaidOutcome>,
+}
+
+impl Default for Raid {
+    fn default() -> Self {
+        Self {
+            remaining: RAID_DURATION,
+            extracting: None,
+            extraction: 0.0,
+            outcome: None,
+        }
+    }
+}
+
+// Written when a raid ends, read by the summary screen
+#[derive(Resource, Debug)]
+pub struct RaidSummary {
+    pub outcome: RaidOutcome,
+    pub duration: f32,
+    pub kept: Vec<Item>,
+    pub lost: Vec<Item>,
+}
+
+// --- SYSTEMS ---
+fn start_raid(mut commands: Commands) {
+    commands.insert_resource(Raid::default());
+}
+
+fn spawn_raid_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font_handle: Handle<Font> = asset_server.load("fonts/ztn.otf");
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            width: percent(100),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        DespawnOnExit(GameState::InGame),
+        children![(
+            RaidText,
+            Text::new(""),
+            TextFont {
+                font: font_handle,
+                font_size: FONT_SIZE,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    ));
+}
+
+fn tick_raid(
+    mut raid: ResMut<Raid>,
+    time: Res<Time>,
+    mut end_writer: MessageWriter<EndRaid>,
+) {
+    if raid.outcome.is_some() {
+        return;
+    }
+
+    raid.remaining -= time.delta_secs(); // Time<Fixed> in FixedUpdate
+    if raid.remaining <= 0.0 {
+        raid.remaining = 0.0;
+        end_writer.write(EndRaid(RaidOutcome::OutOfTime));
+    }
+}
+
+fn tick_extraction(
+    mut raid: ResMut<Raid>,
+    player: Single<Entity, With<Player>>,
+    zone_query: Query<(&ExtractionZone, &CollidingEntities)>,
+    time: Res<Time>,
+    mut end_writer: MessageWriter<EndRaid>,
+) {
+    if raid.outcome.is_some() {
+        return;
+    }
+
+    let zone = zone_query
+        .iter()
+        .find(|(_, colliding)| colliding.contains(&*player))
+        .map(|(zone, _)| zone.seconds);
+
+    // Stepping out starts the countdown over
+    let Some(seconds) = zone else {
+        raid.extracting = None;
+        raid.extraction = 0.0;
+        return;
+    };
+
+    raid.extracting = Some(seconds);
+    raid.extraction += time.delta_secs();
+    if raid.extraction >= seconds {
+        end_writer.write(EndRaid(RaidOutcome::Extracted));
+    }
+}
+
+fn update_raid_ui(raid: Res<Raid>, mut text: Single<&mut Text, With<RaidText>>) {
+    let seconds = raid.remaining.ceil() as u32;
+    let mut line = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+    if let Some(required) = raid.extracting {
+        line.push_str(&format!("   EXTRACTING {:.1}", (required - raid.extraction).max(0.0)));
+    }
+
+    if text.0 != line {
+        text.0 = line;
+    }
+}
+
+// Insured items come back to the stash even when the raid is lost
+fn toggle_insurance(
+    action_state: Res<ActionState>,
+    focus: Res<InventoryFocus>,
+    player: Single<(&mut Inventory, &Equipment), With<Player>>,
+) {
+    if !action_state.just_pressed(Action::Insure) {
+        return;
+    }
+
+    let (mut inventory, equipment) = player.into_inner();
+    let insured = inventory.items.iter().chain(equipment.slots.values()).filter(|item| item.insured).count();
+    let Some(item) = focus.index.and_then(|index| inventory.items.get_mut(index)) else {
+        return;
+    };
+
+    if !item.insured && insured >= INSURED_LIMIT {
+        println!("Only {} items can be insured at once", INSURED_LIMIT);
+        return;
+    }
+    item.insured = !item.insured;
+}
+
+// Settles what the player carried while they still exist, then shows the summary
+fn end_raid(
+    mut commands: Commands,
+    mut end_reader: MessageReader<EndRaid>,
+    mut raid: ResMut<Raid>,
+    mut stash: ResMut<Stash>,
+    registry: Res<ItemRegistry>,
+    player: Single<(&mut Inventory, &mut Equipment), With<Player>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Dying and extracting on the same tick settles on whichever was written first
+    let Some(EndRaid(outcome)) = end_reader.read().next() else {
+        return;
+    };
+    if raid.outcome.is_some() {
+        return;
+    }
+    raid.outcome = Some(*outcome);
+
+    let (mut inventory, mut equipment) = player.into_inner();
+    let carried: Vec<Item> = inventory
+        .items
+        .drain(..)
+        .chain(std::mem::take(&mut equipment.slots).into_values())
+        .collect();
+
+    let (returned, mut lost): (Vec<Item>, Vec<Item>) = match outcome {
+        RaidOutcome::Extracted => (carried, Vec::new()),
+        RaidOutcome::Died | RaidOutcome::OutOfTime | RaidOutcome::Abandoned => carried.into_iter().partition(|item| item.insured),
+    };
+
+    // A full stash turns the rest away
+    let mut kept = Vec::new();
+    for item in returned {
+        if stash.inventory.fits(&item, &registry, STASH_GRID) {
+            stash.inventory.add(item.clone());
+            kept.push(item);
+        }
+        else {
+            lost.push(item);
+        }
+    }
+
+    commands.insert_resource(RaidSummary {
+        outcome: *outcome,
+        duration: RAID_DURATION - raid.remaining,
+        kept,
+        lost,
+    });
+    next_state.set(GameState::RaidSummary);
+}
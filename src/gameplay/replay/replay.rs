@@ -0,0 +1,386 @@
+use std::{fs, path::Path, time::{SystemTime, UNIX_EPOCH}};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::gameplay::controller::plugin::{ControllerInput, MovementAction};
+use crate::gameplay::input::input::{ActionSnapshot, ActionState, TickActions};
+use crate::gameplay::item::items::Item;
+use crate::gameplay::player::aim::AimTarget;
+use crate::gameplay::random::random::GameSeed;
+use crate::gameplay::stage::level::{LevelSystems, StartingLevel};
+use crate::gameplay::save::save::StashSave;
+use crate::gameplay::stash::stash::{Loadout, Stash};
+use crate::gameplay::state::state::{GameState, InGameState};
+
+const REPLAY_DIR: &str = "replays";
+const REPLAY_VERSION: u32 = 1;
+
+// Run with `--record` to capture a session, or `--replay <file>` to play one back. 
+// `--seed <n>` fixes the seed of a live or recorded run. 
+#[derive(Default)]
+pub struct ReplayPlugin {
+    pub record: bool,
+    pub replay: Option<String>, // Played back in place of live input, ahead of recording
+    pub seed: Option<u64>,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let mode = if self.record { ReplayMode::Recording } else { ReplayMode::Off };
+        let mut replay = Replay::new(mode, Vec::new());
+        let mut seed = self.seed.map(GameSeed).unwrap_or_else(GameSeed::from_time);
+
+        if let Some(path) = &self.replay {
+            match read_replay(path) {
+                Ok(file) => {
+                    if let Some(level) = file.level {
+                        app.insert_resource(StartingLevel { name: level });
+                    }
+                    replay = Replay::new(ReplayMode::Playing, file.frames);
+                    replay.loadout = file.loadout;
+                    seed = GameSeed(file.seed);
+                }
+                Err(error) => error!("Failed to load replay {}: {}", path, error),
+            }
+        }
+
+        app
+            .insert_resource(replay)
+            .insert_resource(seed)
+            .add_systems(Update, skip_main_menu.run_if(in_state(GameState::MainMenu).and(replaying)))
+            .add_systems(OnEnter(GameState::InGame), start_replay.before(LevelSystems)) // Before spawn_player takes the loadout
+            .add_systems(OnExit(GameState::InGame), (
+                write_recording.run_if(recording),
+                end_replay.run_if(in_replayed_raid),
+            ))
+            .add_systems(Last, write_recording_on_exit.run_if(recording))
+            // Only ticks that ran the simulation are kept, so pausing or opening the inventory leaves no trace
+            .add_systems(FixedPreUpdate, feed_replay.run_if(in_state(InGameState::Playing).and(replaying)))
+            .add_systems(FixedUpdate, record_tick.after(ControllerInput).run_if(in_state(InGameState::Playing).and(recording)));
+    }
+}
+
+// --- DATA ---
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ReplayFile {
+    pub version: u32,
+    pub seed: u64,
+    #[serde(default)]
+    pub level: Option<String>, // Level the run started on, the usual starting level when missing
+    #[serde(default)]
+    pub loadout: Vec<Item>, // Items the player brought into the raid
+    pub frames: Vec<ReplayFrame>,
+}
+
+// Everything the fixed-step simulation read from the player on one tick
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ReplayFrame {
+    pub movement: Vec<MovementAction>,
+    pub aim: Vec2,
+    pub actions: ActionSnapshot,
+}
+
+// --- ENUMS ---
+#[derive(Debug)]
+pub enum ReplayMode {
+    Off,
+    Recording,
+    Playing,
+    Finished, // Played to the end, live input carries the raid on
+}
+
+// --- RESOURCES ---
+#[derive(Resource, Debug)]
+pub struct Replay {
+    pub mode: ReplayMode,
+    pub tick: usize,
+    pub frames: Vec<ReplayFrame>,
+    pub level: String, // Level the recording started on
+    pub loadout: Vec<Item>,
+    started: bool, // Playback starts the run itself, once
+    kept: Option<StashSave>, // The real stash and loadout, put back once the replayed raid is over
+}
+
+impl Replay {
+    fn new(mode: ReplayMode, frames: Vec<ReplayFrame>) -> Self {
+        Self {
+            mode,
+            tick: 0,
+            frames,
+            level: String::new(),
+            loadout: Vec::new(),
+            started: false,
+            kept: None,
+        }
+    }
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self::new(ReplayMode::Off, Vec::new())
+    }
+}
+
+// --- CONDITIONS ---
+pub fn replaying(replay: Res<Replay>) -> bool {
+    matches!(replay.mode, ReplayMode::Playing)
+}
+
+pub fn recording(replay: Res<Replay>) -> bool {
+    matches!(replay.mode, ReplayMode::Recording)
+}
+
+// From the start of playback until the raid it played ends, even once live input has taken over
+pub fn in_replayed_raid(replay: Res<Replay>) -> bool {
+    matches!(replay.mode, ReplayMode::Playing | ReplayMode::Finished)
+}
+
+fn read_replay(path: &str) -> Result<ReplayFile, String> {
+    let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let file: ReplayFile = ron::from_str(&contents).map_err(|error| error.to_string())?;
+    if file.version != REPLAY_VERSION {
+        return Err(format!("unsupported replay version {}", file.version));
+    }
+    Ok(file)
+}
+
+// --- SYSTEMS ---
+fn start_replay(
+    mut replay: ResMut<Replay>,
+    start: Res<StartingLevel>,
+    stash: Res<Stash>,
+    mut loadout: ResMut<Loadout>,
+) {
+    replay.tick = 0;
+    replay.level = start.name.clone();
+    match replay.mode {
+        ReplayMode::Recording => {
+            replay.frames.clear();
+            replay.loadout = loadout.inventory.items.clone();
+        }
+        // Carried weight changes speed, so the run starts with what the recording started with
+        ReplayMode::Playing => {
+            replay.kept = Some(StashSave { stash: stash.inventory.clone(), loadout: loadout.inventory.clone() });
+            loadout.inventory.items = replay.loadout.clone();
+        }
+        ReplayMode::Off | ReplayMode::Finished => {}
+    }
+}
+
+// Whatever the replayed raid brought home or lost was never the player's
+fn end_replay(mut replay: ResMut<Replay>, mut stash: ResMut<Stash>, mut loadout: ResMut<Loadout>) {
+    if let Some(kept) = replay.kept.take() {
+        stash.inventory = kept.stash;
+        loadout.inventory = kept.loadout;
+    }
+    replay.mode = ReplayMode::Off;
+}
+
+// Live input is off during playback, so nothing could confirm the main menu
+fn skip_main_menu(mut replay: ResMut<Replay>, mut next_state: ResMut<NextState<GameState>>) {
+    if !replay.started {
+        replay.started = true;
+        next_state.set(GameState::InGame);
+    }
+}
+
+fn record_tick(
+    tick: Res<TickActions>,
+    aim: Res<AimTarget>,
+    mut movement_reader: MessageReader<MovementAction>,
+    mut replay: ResMut<Replay>,
+) {
+    let frame = ReplayFrame {
+        movement: movement_reader.read().copied().collect(),
+        aim: aim.position,
+        actions: tick.state.snapshot(),
+    };
+    replay.frames.push(frame);
+    replay.tick += 1;
+}
+
+// Runs before the tick, so every system in it sees the recorded presses exactly once
+fn feed_replay(
+    mut tick: ResMut<TickActions>,
+    mut aim: ResMut<AimTarget>,
+    mut movement_writer: MessageWriter<MovementAction>,
+    mut replay: ResMut<Replay>,
+) {
+    let Some(frame) = replay.frames.get(replay.tick).cloned() else {
+        replay.mode = ReplayMode::Finished;
+        return;
+    };
+
+    movement_writer.write_batch(frame.movement);
+    aim.position = frame.aim;
+    tick.state = ActionState::from_snapshot(&frame.actions);
+    replay.tick += 1;
+}
+
+fn recorded_file(replay: &Replay, seed: &GameSeed) -> ReplayFile {
+    ReplayFile {
+        version: REPLAY_VERSION,
+        seed: seed.0,
+        level: Some(replay.level.clone()),
+        loadout: replay.loadout.clone(),
+        frames: replay.frames.clone(),
+    }
+}
+
+fn write_recording(replay: Res<Replay>, seed: Res<GameSeed>) {
+    if replay.frames.is_empty() {
+        return;
+    }
+
+    let file = recorded_file(&replay, &seed);
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let path = Path::new(REPLAY_DIR).join(format!("replay_{}.ron", stamp));
+
+    let result = fs::create_dir_all(REPLAY_DIR)
+        .map_err(|error| error.to_string())
+        .and_then(|_| ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default()).map_err(|error| error.to_string()))
+        .and_then(|contents| fs::write(&path, contents).map_err(|error| error.to_string()));
+
+    match result {
+        Ok(()) => info!("Recorded {} ticks to {}", file.frames.len(), path.display()),
+        Err(error) => error!("Failed to write replay {}: {}", path.display(), error),
+    }
+}
+
+// Closing the window skips OnExit, so the recording is written here instead
+fn write_recording_on_exit(
+    mut exit_reader: MessageReader<AppExit>,
+    state: Res<State<GameState>>,
+    replay: Res<Replay>,
+    seed: Res<GameSeed>,
+) {
+    if exit_reader.read().next().is_some() && *state.get() == GameState::InGame {
+        write_recording(replay, seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+    use super::*;
+    use crate::gameplay::controller::plugin::tests::{controller_app, run_for, spawn_test_player, SIMULATED_SECONDS};
+    use crate::gameplay::input::input::Action;
+    use crate::gameplay::inventory::inventory::Inventory;
+    use crate::gameplay::player::aim::rotate_aim;
+    use crate::gameplay::save::save::write_stash;
+    use crate::gameplay::stash::stash::{StashFile, StashPlugin};
+
+    // Stands in for the player: sprints right with a dash on the way, walks up, then lets go.
+    // The aim sweeps along the whole time.
+    fn scripted_input(mut tick: ResMut<TickActions>, mut aim: ResMut<AimTarget>, mut count: Local<u32>) {
+        let held: &[Action] = match *count {
+            0..40 => &[Action::MoveRight, Action::Sprint],
+            40..80 => &[Action::MoveUp],
+            _ => &[],
+        };
+        let mut snapshot = ActionSnapshot { pressed: held.to_vec(), ..default() };
+        if *count == 20 {
+            snapshot.pressed.push(Action::Dash);
+            snapshot.just_pressed.push(Action::Dash);
+        }
+
+        tick.state = ActionState::from_snapshot(&snapshot);
+        aim.position = Vec2::new(200.0, *count as f32 * 4.0);
+        *count += 1;
+    }
+
+    fn replay_app(replay: Replay, seed: GameSeed, fps: f64) -> App {
+        let mut app = controller_app(fps);
+        app
+            .add_plugins(ReplayPlugin::default())
+            .init_resource::<StartingLevel>()
+            .init_resource::<Stash>()
+            .init_resource::<Loadout>()
+            .insert_resource(replay)
+            .insert_resource(seed)
+            .add_systems(FixedPreUpdate, scripted_input.run_if(in_state(InGameState::Playing).and(recording)))
+            .add_systems(FixedUpdate, rotate_aim.run_if(in_state(InGameState::Playing)));
+        app
+    }
+
+    // A live run, recorded and written out the way write_recording does
+    fn record(fps: f64) -> (Transform, String) {
+        let mut app = replay_app(Replay::new(ReplayMode::Recording, Vec::new()), GameSeed(7), fps);
+        let player = spawn_test_player(&mut app);
+        run_for(&mut app, SIMULATED_SECONDS);
+
+        let file = recorded_file(app.world().resource::<Replay>(), app.world().resource::<GameSeed>());
+        let written = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default()).unwrap();
+        (*app.world().get::<Transform>(player).unwrap(), written)
+    }
+
+    fn playback(written: &str) -> (Replay, GameSeed) {
+        let file: ReplayFile = ron::from_str(written).unwrap();
+        let mut replay = Replay::new(ReplayMode::Playing, file.frames);
+        replay.loadout = file.loadout;
+        (replay, GameSeed(file.seed))
+    }
+
+    fn play(written: &str, fps: f64) -> Transform {
+        let (replay, seed) = playback(written);
+        let mut app = replay_app(replay, seed, fps);
+        let player = spawn_test_player(&mut app);
+        run_for(&mut app, SIMULATED_SECONDS);
+
+        *app.world().get::<Transform>(player).unwrap()
+    }
+
+    #[test]
+    fn recorded_runs_play_back_the_same() {
+        let (recorded, written) = record(60.0);
+        assert!(recorded.translation.x > 0.0 && recorded.translation.y > 0.0, "the scripted run never moved the player");
+        let file: ReplayFile = ron::from_str(&written).unwrap();
+        assert!(file.frames.iter().any(|frame| frame.actions.just_pressed.contains(&Action::Dash)), "the dash was not recorded");
+
+        for fps in [60.0, 30.0, 144.0] {
+            assert_eq!(play(&written, fps), recorded, "playback at {} FPS diverged from the recorded run", fps);
+        }
+    }
+
+    #[test]
+    fn replayed_raids_leave_the_stash_file_alone() {
+        let path = env::temp_dir().join(format!("replay_stash_{}.ron", process::id()));
+        let kept = StashSave {
+            stash: Inventory { items: vec![Item { id: "revolver".to_string(), stack: 1, insured: false }] },
+            loadout: Inventory::default(),
+        };
+        write_stash(&path, &kept).unwrap();
+        let before = fs::read_to_string(&path).unwrap();
+
+        let (_, written) = record(60.0);
+        let (mut replay, seed) = playback(&written);
+        replay.loadout = vec![Item { id: "bandage".to_string(), stack: 2, insured: false }];
+        replay.frames.truncate(60); // Ends early, so live input takes over for the rest of the raid
+
+        let mut app = replay_app(replay, seed, 60.0);
+        app
+            .add_plugins(StashPlugin)
+            .insert_resource(StashFile::new(path.clone()))
+            .insert_resource(Stash { inventory: kept.stash.clone() })
+            .insert_resource(Loadout { inventory: kept.loadout.clone() });
+        spawn_test_player(&mut app);
+        run_for(&mut app, SIMULATED_SECONDS);
+
+        // The raid brings something home, as end_raid would settle it, and ends
+        app.world_mut().resource_mut::<Stash>().inventory.add(Item { id: "gold".to_string(), stack: 1, insured: false });
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::RaidSummary);
+        app.update();
+        app.update();
+
+        let after = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(after, before, "the replayed raid reached the stash file");
+
+        let ids = |inventory: &Inventory| inventory.items.iter().map(|item| item.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&app.world().resource::<Stash>().inventory), ids(&kept.stash));
+        assert_eq!(ids(&app.world().resource::<Loadout>().inventory), ids(&kept.loadout));
+        assert!(matches!(app.world().resource::<Replay>().mode, ReplayMode::Off));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::gameplay::save::save::{SaveData, SAVE_VERSION};
+use crate::gameplay::stage::level::DEFAULT_LEVEL;
+
+// Hook that upgrades a save written with version `index + 1` to the next version.
+// Files are deserialized leniently first (unknown fields dropped, missing fields defaulted),
+// so a hook only has to patch values whose meaning changed. When a field changes type, give it
+// a new name and have the hook read the old one from the file text into a struct of its own.
+type Migration = fn(&mut SaveData, &str) -> Result<(), String>;
+
+const MIGRATIONS: &[Migration] = &[
+    record_level,
+    remember_levels,
+    keep_stash,
+];
+
+// 1 -> 2: saves did not record which level they were made in, assume the default one
+fn record_level(data: &mut SaveData, _: &str) -> Result<(), String> {
+    if data.world.level.is_none() {
+        data.world.level = Some(DEFAULT_LEVEL.to_string());
+    }
+    Ok(())
+}
+
+// 2 -> 3: other levels were not remembered. `visited` defaults to none, so they restock on the
+// next visit like they did before.
+fn remember_levels(_: &mut SaveData, _: &str) -> Result<(), String> {
+    Ok(())
+}
+
+// 3 -> 4: the stash was not saved with the slot. Without one, loading leaves the current stash
+// alone as it always did.
+fn keep_stash(_: &mut SaveData, _: &str) -> Result<(), String> {
+    Ok(())
+}
+
+// Runs every hook from the file's version up. The version is only stamped once all of them ran.
+pub fn migrate(data: &mut SaveData, source: &str) -> Result<(), String> {
+    for from in data.version..SAVE_VERSION {
+        let Some(migration) = from.checked_sub(1).and_then(|index| MIGRATIONS.get(index as usize)) else {
+            return Err(format!("no migration from version {}", from));
+        };
+        migration(data, source).map_err(|error| format!("version {}: {}", from, error))?;
+    }
+
+    data.version = SAVE_VERSION;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::equipment::equipment::EquipSlot;
+    use crate::gameplay::player::player::Status;
+
+    const VERSION_1: &str = r#"(
+        version: 1,
+        player: (
+            inventory: (items: [(id: "Bandage", stack: 2)]),
+            equipment: (slots: {Hat: (id: "Hat", stack: 1), Holster: (id: "Six Shooter", stack: 1)}),
+            health: 50.0,
+            stamina: 20.0,
+            position: (1.0, 2.0),
+            status: Exhausted,
+        ),
+    )"#;
+
+    fn read(source: &str) -> Result<SaveData, String> {
+        let mut data: SaveData = ron::from_str(source).map_err(|error| error.to_string())?;
+        migrate(&mut data, source)?;
+        Ok(data)
+    }
+
+    #[test]
+    fn version_1_reaches_the_current_version() {
+        let save = read(VERSION_1).unwrap();
+
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(save.world.level.as_deref(), Some(DEFAULT_LEVEL));
+        assert!(save.stash.is_none(), "old saves must leave the stash alone");
+        assert_eq!(save.player.status, Status::Exhausted);
+        assert_eq!(save.player.equipment.get(EquipSlot::Hat).map(|item| item.id.as_str()), Some("Hat"));
+        assert_eq!(save.player.equipment.get(EquipSlot::Holster).map(|item| item.id.as_str()), Some("Six Shooter"));
+    }
+
+    #[test]
+    fn migrated_saves_read_back_unchanged() {
+        let save = read(VERSION_1).unwrap();
+        let reread = read(&ron::to_string(&save).unwrap()).unwrap();
+
+        assert_eq!(reread.player.status, Status::Exhausted);
+        assert_eq!(reread.player.equipment.slots.len(), 2);
+    }
+
+    #[test]
+    fn a_missing_hook_is_an_error() {
+        let mut save = read(VERSION_1).unwrap();
+        save.version = 0;
+        assert!(migrate(&mut save, VERSION_1).is_err());
+    }
+}
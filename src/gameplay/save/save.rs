@@ -0,0 +1,346 @@
+use std::{collections::BTreeMap, fs, io, path::{Path, PathBuf}};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::gameplay::input::input::{Action, ActionState};
+use crate::gameplay::equipment::{equipment::Equipment, hotbar::Hotbar};
+use crate::gameplay::inventory::inventory::{Container, Inventory};
+use crate::gameplay::item::items::{DetectedLoot, Item, Loot};
+use crate::gameplay::player::{
+    health::Health,
+    player::{Player, PlayerStatus, Status},
+    stamina::Stamina,
+};
+use crate::gameplay::random::random::GameSeed;
+use crate::gameplay::save::migration::migrate;
+use crate::gameplay::stash::stash::{Loadout, Stash};
+use crate::gameplay::stage::camera::CameraRig;
+use crate::gameplay::stage::level::{
+    build_level, capture_level, resolve_level, unload_level, CurrentLevel, LevelEntity, LevelState, VisitedLevels,
+};
+use crate::gameplay::state::state::InGameState;
+
+// Bump this whenever SaveData changes shape and add a matching hook in migration.rs
+pub const SAVE_VERSION: u32 = 4;
+pub const SAVE_SLOTS: usize = 3;
+const SAVE_DIR: &str = "saves";
+const STASH_FILE: &str = "stash.ron";
+
+pub struct SavePlugin;
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(ActiveSaveSlot(0))
+            .add_message::<SaveGame>()
+            .add_message::<LoadGame>()
+            .add_systems(Update, (
+                save_hotkeys,
+                save_game.after(save_hotkeys),
+                load_game.after(save_hotkeys),
+            ).run_if(in_state(InGameState::Playing)));
+    }
+}
+
+// --- SAVE DATA ---
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SaveData {
+    pub version: u32,
+    pub player: PlayerSave,
+    #[serde(default)]
+    pub world: WorldSave,
+    // The stash as it stood when the slot was saved. Loading rolls it back too, or whatever was
+    // carried out since would be both in the stash and back in the player's hands
+    #[serde(default)]
+    pub stash: Option<StashSave>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlayerSave {
+    pub inventory: Inventory,
+    #[serde(default)]
+    pub equipment: Equipment,
+    #[serde(default)]
+    pub hotbar: Hotbar,
+    pub health: f32,
+    pub stamina: f32,
+    pub position: [f32; 2],
+    #[serde(default)]
+    pub rotation: f32,
+    pub status: Status,
+    #[serde(default)]
+    pub status_duration: f32,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct WorldSave {
+    #[serde(default)]
+    pub level: Option<String>, // Filled in by migration for saves from before level transitions
+    pub loot: Vec<LootSave>,
+    pub containers: Vec<ContainerSave>,
+    #[serde(default)]
+    pub visited: BTreeMap<String, LevelState>, // Other levels the raid has already been through
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LootSave {
+    pub item: Item,
+    pub position: [f32; 2],
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ContainerSave {
+    pub id: String,
+    pub inventory: Inventory,
+}
+
+// Kept apart from the slots: the stash outlives every raid and every quick save
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct StashSave {
+    #[serde(default)]
+    pub stash: Inventory,
+    #[serde(default)]
+    pub loadout: Inventory,
+}
+
+// Only the version is read first so older files can be routed through migrations.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Serialize(ron::Error),
+    Parse(ron::error::SpannedError),
+    UnsupportedVersion(u32),
+    Migration(String),
+}
+
+// --- MESSAGES ---
+#[derive(Message)]
+pub struct SaveGame {
+    pub slot: usize,
+}
+
+#[derive(Message)]
+pub struct LoadGame {
+    pub slot: usize,
+}
+
+// --- RESOURCES ---
+#[derive(Resource)]
+pub struct ActiveSaveSlot(pub usize);
+
+// --- FILE IO ---
+pub fn slot_path(slot: usize) -> PathBuf {
+    PathBuf::from(SAVE_DIR).join(format!("slot_{}.ron", slot))
+}
+
+pub fn write_save(slot: usize, data: &SaveData) -> Result<(), SaveError> {
+    let contents = ron::ser::to_string_pretty(data, ron::ser::PrettyConfig::default())
+        .map_err(SaveError::Serialize)?;
+
+    fs::create_dir_all(SAVE_DIR).map_err(SaveError::Io)?;
+    fs::write(slot_path(slot), contents).map_err(SaveError::Io)
+}
+
+pub fn read_save(slot: usize) -> Result<SaveData, SaveError> {
+    let contents = fs::read_to_string(slot_path(slot)).map_err(SaveError::Io)?;
+    let header: SaveHeader = ron::from_str(&contents).map_err(SaveError::Parse)?;
+    if header.version == 0 || header.version > SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(header.version));
+    }
+
+    // Unknown fields are ignored and missing ones fall back to their defaults,
+    // so the hooks only need to fix up what changed meaning between versions.
+    let mut data: SaveData = ron::from_str(&contents).map_err(SaveError::Parse)?;
+    migrate(&mut data, &contents).map_err(SaveError::Migration)?;
+    Ok(data)
+}
+
+pub fn stash_path() -> PathBuf {
+    PathBuf::from(SAVE_DIR).join(STASH_FILE)
+}
+
+pub fn write_stash(path: &Path, data: &StashSave) -> Result<(), SaveError> {
+    let contents = ron::ser::to_string_pretty(data, ron::ser::PrettyConfig::default())
+        .map_err(SaveError::Serialize)?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(SaveError::Io)?;
+    }
+    fs::write(path, contents).map_err(SaveError::Io)
+}
+
+pub fn read_stash(path: &Path) -> Result<StashSave, SaveError> {
+    let contents = fs::read_to_string(path).map_err(SaveError::Io)?;
+    ron::from_str(&contents).map_err(SaveError::Parse)
+}
+
+// --- SYSTEMS ---
+fn save_hotkeys(
+    action_state: Res<ActionState>,
+    mut active_slot: ResMut<ActiveSaveSlot>,
+    mut save_writer: MessageWriter<SaveGame>,
+    mut load_writer: MessageWriter<LoadGame>,
+) {
+    if action_state.just_pressed(Action::CycleSaveSlot) {
+        active_slot.0 = (active_slot.0 + 1) % SAVE_SLOTS;
+        println!("Active save slot: {}", active_slot.0);
+    }
+
+    if action_state.just_pressed(Action::QuickSave) {
+        save_writer.write(SaveGame { slot: active_slot.0 });
+    }
+
+    if action_state.just_pressed(Action::QuickLoad) {
+        load_writer.write(LoadGame { slot: active_slot.0 });
+    }
+}
+
+fn save_game(
+    mut save_reader: MessageReader<SaveGame>,
+    player: Single<(
+        &Inventory,
+        &Equipment,
+        &Hotbar,
+        &Health,
+        &Stamina,
+        &Transform,
+        &PlayerStatus,
+    ), With<Player>>,
+    loot_query: Query<(&Item, &Transform), (With<Loot>, Without<Player>)>,
+    container_query: Query<(&Container, &Inventory), Without<Player>>,
+    current: Res<CurrentLevel>,
+    visited: Res<VisitedLevels>,
+    stash: Res<Stash>,
+    loadout: Res<Loadout>,
+) {
+    let (inventory, equipment, hotbar, health, stamina, transform, status) = *player;
+
+    for request in save_reader.read() {
+        let LevelState { loot, containers } = capture_level(&loot_query, &container_query);
+        let data = SaveData {
+            version: SAVE_VERSION,
+            player: PlayerSave {
+                inventory: inventory.clone(),
+                equipment: equipment.clone(),
+                hotbar: hotbar.clone(),
+                health: health.current,
+                stamina: stamina.current,
+                position: transform.translation.truncate().to_array(),
+                rotation: transform.rotation.to_euler(EulerRot::XYZ).2,
+                status: status.condition,
+                status_duration: status.duration,
+            },
+            world: WorldSave {
+                level: Some(current.name.clone()),
+                loot,
+                containers,
+                visited: visited.levels.clone(),
+            },
+            stash: Some(StashSave {
+                stash: stash.inventory.clone(),
+                loadout: loadout.inventory.clone(),
+            }),
+        };
+
+        match write_save(request.slot, &data) {
+            Ok(()) => println!("Saved game to slot {}", request.slot),
+            Err(error) => println!("Failed to save slot {}: {:?}", request.slot, error),
+        }
+    }
+}
+
+fn load_game(
+    mut commands: Commands,
+    mut load_reader: MessageReader<LoadGame>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    asset_server: Res<AssetServer>,
+    mut current: ResMut<CurrentLevel>,
+    seed: Res<GameSeed>,
+    mut detected: ResMut<DetectedLoot>,
+    mut visited: ResMut<VisitedLevels>,
+    mut stash: ResMut<Stash>,
+    mut loadout: ResMut<Loadout>,
+    player: Single<(
+        &mut Inventory,
+        &mut Equipment,
+        &mut Hotbar,
+        &mut Health,
+        &mut Stamina,
+        &mut Transform,
+        &mut LinearVelocity,
+        &mut PlayerStatus,
+    ), With<Player>>,
+    level_query: Query<Entity, With<LevelEntity>>,
+    mut rig_query: Query<&mut CameraRig>,
+) {
+    let Some(request) = load_reader.read().last() else {
+        return;
+    };
+
+    let data = match read_save(request.slot) {
+        Ok(data) => data,
+        Err(error) => {
+            println!("Failed to load slot {}: {:?}", request.slot, error);
+            return;
+        }
+    };
+
+    let level_name = data.world.level.clone().unwrap_or_else(|| current.name.clone());
+    let level = match resolve_level(&level_name, &seed) {
+        Ok(level) => level,
+        Err(error) => {
+            println!("Failed to load slot {}: level {}", request.slot, error);
+            return;
+        }
+    };
+
+    // The saved floor loot and container contents replace what the level places
+    let state = LevelState { loot: data.world.loot, containers: data.world.containers };
+
+    // The level is rebuilt from scratch, so any detections point at despawned entities
+    unload_level(&mut commands, &level_query);
+    build_level(&mut commands, &mut meshes, &mut materials, &asset_server, &seed, level, Some(&state));
+    current.name = level_name;
+    visited.levels = data.world.visited;
+    detected.items.clear();
+    detected.index = 0;
+
+    let (
+        mut inventory,
+        mut equipment,
+        mut hotbar,
+        mut health,
+        mut stamina,
+        mut transform,
+        mut velocity,
+        mut status,
+    ) = player.into_inner();
+
+    *inventory = data.player.inventory;
+    *equipment = data.player.equipment;
+    *hotbar = data.player.hotbar;
+    health.current = data.player.health.min(health.max);
+    stamina.current = data.player.stamina.min(stamina.max);
+    transform.translation.x = data.player.position[0];
+    transform.translation.y = data.player.position[1];
+    transform.rotation = Quat::from_rotation_z(data.player.rotation);
+    velocity.0 = Vec2::ZERO;
+    status.condition = data.player.status;
+    status.duration = data.player.status_duration;
+
+    for mut rig in &mut rig_query {
+        rig.focus = transform.translation.truncate();
+    }
+
+    if let Some(saved) = data.stash {
+        stash.inventory = saved.stash;
+        loadout.inventory = saved.loadout;
+    }
+
+    println!("Loaded game from slot {}", request.slot);
+}
@@ -1,21 +1,140 @@
-use crate::gameplay::player::player::Player; 
-use bevy::prelude::*;
+use crate::gameplay::player::{aim::AimTarget, health::Damage, player::Player}; 
+use bevy::{input::mouse::MouseWheel, prelude::*, window::PrimaryWindow};
 
 const CAMERA_DECAY_RATE: f32 = 2.;
 
+// Zoom, as orthographic scale. Higher shows more of the map
+const ZOOM_MIN: f32 = 0.5;
+const ZOOM_MAX: f32 = 2.0;
+const ZOOM_STEP: f32 = 0.1; // Fraction of the current zoom per wheel notch
+const ZOOM_DECAY_RATE: f32 = 8.;
+
+// Look-ahead toward the aim target
+const LOOK_AHEAD: f32 = 0.3; // Fraction of the distance to the aim target
+const LOOK_AHEAD_MAX: f32 = 250.0;
+
+// Shake grows with trauma squared so small hits stay subtle
+const TRAUMA_DECAY: f32 = 1.5; // Per second
+const SHAKE_OFFSET: f32 = 30.0;
+const SHAKE_ANGLE: f32 = 0.05; // Radians
+const SHAKE_FREQUENCY: f32 = 25.0;
+const DAMAGE_TRAUMA: f32 = 0.02; // Per point of damage taken
+
+// --- COMPONENTS ---
+// Smoothed camera state. The shake is added on top of focus each frame, never smoothed into it
+#[derive(Component)]
+pub struct CameraRig {
+    pub focus: Vec2,
+    pub zoom: f32,
+    pub target_zoom: f32,
+    pub trauma: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self { focus: Vec2::ZERO, zoom: 1.0, target_zoom: 1.0, trauma: 0.0 }
+    }
+}
+
+// --- RESOURCES ---
+// World space extents the view stays inside, set by whoever builds the map
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct MapBounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+// Cutscenes take the camera away from the player until cleared
+#[derive(Resource, Default)]
+pub struct CinematicCamera {
+    pub target: Option<Vec2>,
+    pub zoom: Option<f32>,
+}
+
+// --- MESSAGES ---
+// Adds trauma to the camera, clamped to 1.0
+#[derive(Message)]
+pub struct CameraShake(pub f32);
+
 pub fn init_camera(mut commands: Commands) {
-    commands.spawn(Camera2d);
+    commands.spawn((Camera2d, CameraRig::default()));
+}
+
+// --- SYSTEMS ---
+pub fn zoom_camera(
+    mut wheel_reader: MessageReader<MouseWheel>,
+    mut rig: Single<&mut CameraRig>,
+) {
+    for wheel in wheel_reader.read() {
+        // Sideways scrolling has no y, and 0.0.signum() would still zoom in
+        if wheel.y == 0.0 {
+            continue;
+        }
+        let zoom = rig.target_zoom * (1.0 - wheel.y.signum() * ZOOM_STEP);
+        rig.target_zoom = zoom.clamp(ZOOM_MIN, ZOOM_MAX);
+    }
+}
+
+pub fn add_trauma(
+    mut shake_reader: MessageReader<CameraShake>,
+    mut damage_reader: MessageReader<Damage>,
+    player: Single<Entity, With<Player>>,
+    mut rig: Single<&mut CameraRig>,
+) {
+    let mut trauma: f32 = shake_reader.read().map(|shake| shake.0).sum();
+    trauma += damage_reader
+        .read()
+        .filter(|damage| damage.target == *player)
+        .map(|damage| damage.amount * DAMAGE_TRAUMA)
+        .sum::<f32>();
+
+    rig.trauma = (rig.trauma + trauma).min(1.0);
 }
 
 pub fn update_camera(
-    mut camera: Single<&mut Transform, (With<Camera2d>, Without<Player>)>,
+    camera: Single<(&mut Transform, &mut Projection, &mut CameraRig), (With<Camera2d>, Without<Player>)>,
     player: Single<&Transform, (With<Player>, Without<Camera2d>)>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    aim: Res<AimTarget>,
+    bounds: Option<Res<MapBounds>>,
+    cinematic: Res<CinematicCamera>,
     time: Res<Time>,
 ) {
-    let Vec3 { x, y, .. } = player.translation;
-    let direction = Vec3::new(x, y, camera.translation.z);
+    let (mut transform, mut projection, mut rig) = camera.into_inner();
+    let delta_time = time.delta_secs();
+
+    // Zoom
+    let target_zoom = cinematic.zoom.unwrap_or(rig.target_zoom);
+    rig.zoom.smooth_nudge(&target_zoom, ZOOM_DECAY_RATE, delta_time);
+    if let Projection::Orthographic(orthographic) = projection.as_mut() {
+        orthographic.scale = rig.zoom;
+    }
+
+    // Follow the player, leaning toward where they aim
+    let target = cinematic.target.unwrap_or_else(|| {
+        let position = player.translation.truncate();
+        position + ((aim.position - position) * LOOK_AHEAD).clamp_length_max(LOOK_AHEAD_MAX)
+    });
+    let mut focus = rig.focus;
+    focus.smooth_nudge(&target, CAMERA_DECAY_RATE, delta_time);
+
+    // Keep the view inside the map, centring it when the map is smaller than the view
+    if let Some(bounds) = bounds {
+        let half_view = window.size() * 0.5 * rig.zoom;
+        for axis in 0..2 {
+            let min = bounds.min[axis] + half_view[axis];
+            let max = bounds.max[axis] - half_view[axis];
+            focus[axis] = if min > max { (bounds.min[axis] + bounds.max[axis]) * 0.5 } else { focus[axis].clamp(min, max) };
+        }
+    }
+    rig.focus = focus;
+
+    // Shake
+    rig.trauma = (rig.trauma - TRAUMA_DECAY * delta_time).max(0.0);
+    let shake = rig.trauma * rig.trauma;
+    let t = time.elapsed_secs() * SHAKE_FREQUENCY;
+    let offset = Vec2::new((t * 1.3).sin() + (t * 2.9).sin() * 0.5, (t * 1.7).cos() + (t * 3.1).sin() * 0.5) / 1.5;
 
-    camera
-        .translation
-        .smooth_nudge(&direction, CAMERA_DECAY_RATE, time.delta_secs());
+    transform.translation = (focus + offset * SHAKE_OFFSET * shake).extend(transform.translation.z);
+    transform.rotation = Quat::from_rotation_z((t * 2.3).sin() * SHAKE_ANGLE * shake);
 }
@@ -0,0 +1,499 @@
+use std::collections::{BTreeMap, VecDeque};
+use bevy::prelude::*;
+use crate::gameplay::item::items::Item;
+use crate::gameplay::raid::raid::EXTRACTION_TIME;
+use crate::gameplay::random::random::{GameSeed, SeededRng};
+use crate::gameplay::stage::level::{ColliderShape, LevelDefinition, LevelObject, TileKind, TileLayer};
+
+// Loaded in place of a level file when CurrentLevel names it
+pub const GENERATED_LEVEL: &str = "town";
+
+const TOWN_WIDTH: u32 = 48;
+const TOWN_HEIGHT: u32 = 32;
+const TILE_SIZE: f32 = 100.0;
+const MIN_ROOM: u32 = 6; // Smallest room side in tiles, walls excluded
+const MAX_DEPTH: u32 = 4;
+const FURNITURE_MARGIN: u32 = 2; // Tiles kept clear along every wall so doors always connect
+const MAX_ATTEMPTS: u64 = 16;
+const RNG_STREAM: u64 = 0x70_57_4E; // Keeps generation draws apart from floor variants
+
+const WALL: char = '#';
+const DOOR: char = '+';
+const STREET: char = 'd';
+
+// Loot drawn for floor spawns and container contents, heavier weight is more common
+const LOOT_TABLE: &[(&str, u32)] = &[("Bandage", 8), ("Six Shooter", 2), ("Shotgun", 1)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoomKind {
+    Bar,
+    CardTables,
+    BackRoom,
+    Upstairs, // The stairwell and bedrooms, laid out on the same floor plan for now
+}
+
+impl RoomKind {
+    fn symbol(&self) -> char {
+        match self {
+            RoomKind::Bar => 'w',
+            RoomKind::CardTables => 'c',
+            RoomKind::BackRoom => 'b',
+            RoomKind::Upstairs => 'u',
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            RoomKind::Bar => "bar",
+            RoomKind::CardTables => "card_tables",
+            RoomKind::BackRoom => "back_room",
+            RoomKind::Upstairs => "upstairs",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Room {
+    pub kind: RoomKind,
+    pub rect: URect, // Floor tiles, walls excluded
+}
+
+// A wall laid down by one split, doors are cut into it once every wall exists
+struct Split {
+    vertical: bool, // Wall runs along y at a fixed x
+    at: u32,
+    from: u32,
+    to: u32,
+}
+
+struct Layout {
+    cells: Vec<char>,
+    blocked: Vec<bool>, // Furniture that blocks movement, for the connectivity check
+    rooms: Vec<Room>,
+    objects: Vec<LevelObject>,
+    colliders: Vec<ColliderShape>,
+}
+
+impl Layout {
+    fn index(&self, x: u32, y: u32) -> usize {
+        (y * TOWN_WIDTH + x) as usize
+    }
+
+    fn get(&self, x: u32, y: u32) -> char {
+        self.cells[self.index(x, y)]
+    }
+
+    fn set(&mut self, x: u32, y: u32, symbol: char) {
+        let index = self.index(x, y);
+        self.cells[index] = symbol;
+    }
+
+    fn walkable(&self, x: u32, y: u32) -> bool {
+        let symbol = self.get(x, y);
+        symbol != WALL && symbol != STREET && !self.blocked[self.index(x, y)]
+    }
+}
+
+// Centre of a tile span in world space, the map being centred on the origin
+fn world(x: f32, y: f32) -> [f32; 2] {
+    [
+        (x - TOWN_WIDTH as f32 * 0.5) * TILE_SIZE,
+        (y - TOWN_HEIGHT as f32 * 0.5) * TILE_SIZE,
+    ]
+}
+
+fn roll_loot(rng: &mut SeededRng) -> String {
+    let total: u32 = LOOT_TABLE.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rng.range(0, total as i32) as u32;
+    for (id, weight) in LOOT_TABLE {
+        if roll < *weight {
+            return id.to_string();
+        }
+        roll -= weight;
+    }
+    LOOT_TABLE[0].0.to_string()
+}
+
+// Lays out a saloon from the seed. Retries with a fresh stream if a layout comes out disconnected,
+// and gives up with an error rather than hand back a town with rooms cut off from the bar
+pub fn generate_town(seed: &GameSeed) -> Result<LevelDefinition, String> {
+    for attempt in 0..MAX_ATTEMPTS {
+        let layout = build_layout(&mut seed.rng(RNG_STREAM + attempt));
+        let unreachable = unreachable_rooms(&layout);
+        if unreachable.is_empty() {
+            return Ok(into_level(layout));
+        }
+        println!("Generated town {} left {:?} unreachable, retrying", attempt, unreachable);
+    }
+
+    Err(format!("{}: seed {} left rooms unreachable after {} attempts", GENERATED_LEVEL, seed.0, MAX_ATTEMPTS))
+}
+
+fn build_layout(rng: &mut SeededRng) -> Layout {
+    let size = (TOWN_WIDTH * TOWN_HEIGHT) as usize;
+    let mut layout = Layout {
+        cells: vec![STREET; size],
+        blocked: vec![false; size],
+        rooms: Vec::new(),
+        objects: Vec::new(),
+        colliders: Vec::new(),
+    };
+
+    // One tile of street, then the outer wall around the building
+    let building = URect::new(1, 1, TOWN_WIDTH - 1, TOWN_HEIGHT - 1);
+    for y in building.min.y..building.max.y {
+        for x in building.min.x..building.max.x {
+            let edge = x == building.min.x || y == building.min.y || x == building.max.x - 1 || y == building.max.y - 1;
+            layout.set(x, y, if edge { WALL } else { RoomKind::Bar.symbol() });
+        }
+    }
+
+    let interior = URect::new(2, 2, TOWN_WIDTH - 2, TOWN_HEIGHT - 2);
+    let mut leaves = Vec::new();
+    let mut splits = Vec::new();
+    split(interior, 0, rng, &mut leaves, &mut splits);
+
+    for wall in &splits {
+        for along in wall.from..wall.to {
+            let (x, y) = if wall.vertical { (wall.at, along) } else { (along, wall.at) };
+            layout.set(x, y, WALL);
+        }
+    }
+
+    // A door where both sides are floor, never where a deeper wall meets this one
+    for wall in &splits {
+        let candidates: Vec<u32> = (wall.from..wall.to)
+            .filter(|along| {
+                let (a, b) = if wall.vertical {
+                    (layout.get(wall.at - 1, *along), layout.get(wall.at + 1, *along))
+                } else {
+                    (layout.get(*along, wall.at - 1), layout.get(*along, wall.at + 1))
+                };
+                a != WALL && b != WALL
+            })
+            .collect();
+        if candidates.is_empty() {
+            continue;
+        }
+
+        let along = candidates[rng.range(0, candidates.len() as i32) as usize];
+        let (x, y) = if wall.vertical { (wall.at, along) } else { (along, wall.at) };
+        layout.set(x, y, DOOR);
+    }
+
+    // Biggest rooms get the rooms that need the space
+    leaves.sort_by_key(|rect: &URect| std::cmp::Reverse(rect.size().element_product()));
+    for (index, rect) in leaves.into_iter().enumerate() {
+        let kind = match index {
+            0 => RoomKind::Bar,
+            1 => RoomKind::CardTables,
+            2 => RoomKind::BackRoom,
+            3 => RoomKind::Upstairs,
+            _ => [RoomKind::CardTables, RoomKind::BackRoom, RoomKind::Upstairs][rng.range(0, 3) as usize],
+        };
+        for y in rect.min.y..rect.max.y {
+            for x in rect.min.x..rect.max.x {
+                layout.set(x, y, kind.symbol());
+            }
+        }
+        layout.rooms.push(Room { kind, rect });
+    }
+
+    for index in 0..layout.rooms.len() {
+        furnish(&mut layout, index, rng);
+    }
+
+    layout
+}
+
+// Binary space partition, each split leaves a one tile wall between its halves
+fn split(rect: URect, depth: u32, rng: &mut SeededRng, leaves: &mut Vec<URect>, splits: &mut Vec<Split>) {
+    let size = rect.size();
+    let vertical = size.x >= size.y;
+    let span = if vertical { size.x } else { size.y };
+    if depth >= MAX_DEPTH || span < MIN_ROOM * 2 + 1 {
+        leaves.push(rect);
+        return;
+    }
+
+    let start = if vertical { rect.min.x } else { rect.min.y };
+    let at = rng.range((start + MIN_ROOM) as i32, (start + span - MIN_ROOM) as i32) as u32;
+    let (first, second, from, to) = if vertical {
+        (URect::new(rect.min.x, rect.min.y, at, rect.max.y), URect::new(at + 1, rect.min.y, rect.max.x, rect.max.y), rect.min.y, rect.max.y)
+    } else {
+        (URect::new(rect.min.x, rect.min.y, rect.max.x, at), URect::new(rect.min.x, at + 1, rect.max.x, rect.max.y), rect.min.x, rect.max.x)
+    };
+
+    splits.push(Split { vertical, at, from, to });
+    split(first, depth + 1, rng, leaves, splits);
+    split(second, depth + 1, rng, leaves, splits);
+}
+
+fn block(layout: &mut Layout, min: UVec2, max: UVec2) {
+    for y in min.y..max.y {
+        for x in min.x..max.x {
+            let index = layout.index(x, y);
+            layout.blocked[index] = true;
+        }
+    }
+}
+
+// Furniture stays inside the margin so the ring of floor along the walls links every door
+fn furnish(layout: &mut Layout, index: usize, rng: &mut SeededRng) {
+    let Room { kind, rect } = layout.rooms[index];
+    let inner = URect::new(
+        rect.min.x + FURNITURE_MARGIN,
+        rect.min.y + FURNITURE_MARGIN,
+        rect.max.x.saturating_sub(FURNITURE_MARGIN).max(rect.min.x + FURNITURE_MARGIN),
+        rect.max.y.saturating_sub(FURNITURE_MARGIN).max(rect.min.y + FURNITURE_MARGIN),
+    );
+    if inner.is_empty() {
+        return;
+    }
+    let center = rect.as_rect().center();
+    let random_spot = |rng: &mut SeededRng| {
+        UVec2::new(
+            rng.range(inner.min.x as i32, inner.max.x as i32) as u32,
+            rng.range(inner.min.y as i32, inner.max.y as i32) as u32,
+        )
+    };
+
+    match kind {
+        RoomKind::Bar => {
+            layout.objects.push(LevelObject::PlayerSpawn { position: world(center.x, center.y) });
+
+            // Counter along the top of the room
+            let (min, max) = (UVec2::new(inner.min.x, inner.max.y - 1), UVec2::new(inner.max.x, inner.max.y));
+            let middle = (min + max).as_vec2() * 0.5;
+            layout.colliders.push(ColliderShape::Rectangle {
+                position: world(middle.x, middle.y),
+                size: [(max.x - min.x) as f32 * TILE_SIZE, TILE_SIZE * 0.8],
+            });
+            block(layout, min, max);
+        }
+        RoomKind::CardTables => {
+            let mut table = 0;
+            for y in (inner.min.y + 1..inner.max.y).step_by(3) {
+                for x in (inner.min.x + 1..inner.max.x).step_by(3) {
+                    if !rng.chance(0.7) {
+                        continue;
+                    }
+                    layout.colliders.push(ColliderShape::Circle {
+                        position: world(x as f32 + 0.5, y as f32 + 0.5),
+                        radius: TILE_SIZE * 0.45,
+                    });
+                    block(layout, UVec2::new(x, y), UVec2::new(x + 1, y + 1));
+                    table += 1;
+                }
+            }
+            if table == 0 {
+                layout.objects.push(LevelObject::Loot { item: roll_loot(rng), stack: 1, position: world(center.x, center.y) });
+            }
+
+            // A lookout walking the floor ring around the tables
+            let (first, last) = (rect.min.as_vec2() + 0.5, rect.max.as_vec2() - 0.5);
+            let patrol = vec![world(first.x, first.y), world(last.x, first.y), world(last.x, last.y), world(first.x, last.y)];
+            layout.objects.push(LevelObject::Npc { archetype: "Outlaw".to_string(), position: patrol[2], patrol });
+        }
+        RoomKind::BackRoom => {
+            for number in 0..2 {
+                let spot = random_spot(rng);
+                if layout.blocked[layout.index(spot.x, spot.y)] {
+                    continue;
+                }
+                let items = (0..rng.range(1, 4)).map(|_| Item { id: roll_loot(rng), stack: 1, insured: false }).collect();
+                layout.objects.push(LevelObject::Container {
+                    id: format!("town_{}_{}_{}", kind.label(), index, number),
+                    position: world(spot.x as f32 + 0.5, spot.y as f32 + 0.5),
+                    size: [TILE_SIZE * 0.9, TILE_SIZE * 0.6],
+                    items,
+                });
+                block(layout, spot, spot + UVec2::ONE);
+            }
+
+            // The back door out of town, on the floor ring so furniture never covers it
+            if !layout.objects.iter().any(|object| matches!(object, LevelObject::Extraction { .. })) {
+                layout.objects.push(LevelObject::Extraction {
+                    position: world(rect.min.x as f32 + 0.5, rect.min.y as f32 + 0.5),
+                    size: [TILE_SIZE, TILE_SIZE],
+                    seconds: EXTRACTION_TIME,
+                });
+            }
+
+            // Guards the far corner from the way out
+            layout.objects.push(LevelObject::Npc {
+                archetype: "Gunslinger".to_string(),
+                position: world(rect.max.x as f32 - 0.5, rect.max.y as f32 - 0.5),
+                patrol: Vec::new(),
+            });
+
+            // Crates move when shoved, so they never count as blocking
+            let spot = random_spot(rng);
+            layout.objects.push(LevelObject::Crate { position: world(spot.x as f32 + 0.5, spot.y as f32 + 0.5), size: [40.0, 40.0] });
+        }
+        RoomKind::Upstairs => {
+            // Beds along the left of the room, two tiles long
+            for y in (inner.min.y..inner.max.y.saturating_sub(1)).step_by(3) {
+                layout.colliders.push(ColliderShape::Rectangle {
+                    position: world(inner.min.x as f32 + 0.5, y as f32 + 1.0),
+                    size: [TILE_SIZE * 0.8, TILE_SIZE * 1.8],
+                });
+                block(layout, UVec2::new(inner.min.x, y), UVec2::new(inner.min.x + 1, y + 2));
+            }
+
+            let spot = UVec2::new(inner.max.x - 1, inner.max.y - 1);
+            if !layout.blocked[layout.index(spot.x, spot.y)] {
+                layout.objects.push(LevelObject::Container {
+                    id: format!("town_{}_{}_dresser", kind.label(), index),
+                    position: world(spot.x as f32 + 0.5, spot.y as f32 + 0.5),
+                    size: [TILE_SIZE * 0.9, TILE_SIZE * 0.5],
+                    items: vec![Item { id: roll_loot(rng), stack: 1, insured: false }],
+                });
+                block(layout, spot, spot + UVec2::ONE);
+            }
+
+            let spot = random_spot(rng);
+            if !layout.blocked[layout.index(spot.x, spot.y)] {
+                layout.objects.push(LevelObject::Npc {
+                    archetype: "Drifter".to_string(),
+                    position: world(spot.x as f32 + 0.5, spot.y as f32 + 0.5),
+                    patrol: Vec::new(),
+                });
+            }
+        }
+    }
+
+    // Floor loot, the spawn points raids draw from
+    for _ in 0..rng.range(1, 3) {
+        let spot = random_spot(rng);
+        if layout.blocked[layout.index(spot.x, spot.y)] {
+            continue;
+        }
+        layout.objects.push(LevelObject::Loot { item: roll_loot(rng), stack: 1, position: world(spot.x as f32 + 0.5, spot.y as f32 + 0.5) });
+    }
+}
+
+// Flood fill from the bar, any room without a reached tile is returned
+fn unreachable_rooms(layout: &Layout) -> Vec<RoomKind> {
+    let Some(bar) = layout.rooms.iter().find(|room| room.kind == RoomKind::Bar) else {
+        return layout.rooms.iter().map(|room| room.kind).collect();
+    };
+
+    let mut reached = vec![false; layout.cells.len()];
+    let mut queue = VecDeque::new();
+    for y in bar.rect.min.y..bar.rect.max.y {
+        for x in bar.rect.min.x..bar.rect.max.x {
+            if layout.walkable(x, y) {
+                reached[layout.index(x, y)] = true;
+                queue.push_back(UVec2::new(x, y));
+                break;
+            }
+        }
+        if !queue.is_empty() {
+            break;
+        }
+    }
+
+    while let Some(cell) = queue.pop_front() {
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            let (nx, ny) = (cell.x as i32 + dx, cell.y as i32 + dy);
+            if nx < 0 || ny < 0 || nx >= TOWN_WIDTH as i32 || ny >= TOWN_HEIGHT as i32 {
+                continue;
+            }
+            let (nx, ny) = (nx as u32, ny as u32);
+            let index = layout.index(nx, ny);
+            if !reached[index] && layout.walkable(nx, ny) {
+                reached[index] = true;
+                queue.push_back(UVec2::new(nx, ny));
+            }
+        }
+    }
+
+    layout
+        .rooms
+        .iter()
+        .filter(|room| {
+            !(room.rect.min.y..room.rect.max.y)
+                .any(|y| (room.rect.min.x..room.rect.max.x).any(|x| reached[layout.index(x, y)]))
+        })
+        .map(|room| room.kind)
+        .collect()
+}
+
+fn into_level(layout: Layout) -> LevelDefinition {
+    let rows = |keep: &dyn Fn(char) -> char| -> Vec<String> {
+        (0..TOWN_HEIGHT)
+            .rev()
+            .map(|y| (0..TOWN_WIDTH).map(|x| keep(layout.get(x, y))).collect())
+            .collect()
+    };
+
+    let kind = |images: &[&str]| TileKind { images: images.iter().map(|image| image.to_string()).collect(), solid: false, transitions: Vec::new() };
+    let floor_tiles = BTreeMap::from([
+        ('w', kind(&["floors/floor4_no_hole.png", "floors/other_floor_2.png"])),
+        ('c', kind(&["floors/other_floor.png"])),
+        ('b', kind(&["floors/yucky_floor.png", "floors/floor.png"])),
+        ('u', kind(&["floors/floor.png"])),
+        (STREET, kind(&["floors/dirt.png"])),
+    ]);
+
+    let floor = TileLayer {
+        name: "Floor".to_string(),
+        tileset: None,
+        tiles: floor_tiles,
+        z: 0.0,
+        fill: None,
+        // Walls and doorways sit on bar planks
+        rows: rows(&|symbol| if symbol == WALL || symbol == DOOR { 'w' } else { symbol }),
+    };
+    let walls = TileLayer {
+        name: "Walls".to_string(),
+        tileset: None,
+        tiles: BTreeMap::from([(WALL, TileKind { solid: true, ..kind(&["floors/floor3.png"]) })]),
+        z: 0.2,
+        fill: None,
+        rows: rows(&|symbol| if symbol == WALL { WALL } else { '.' }),
+    };
+
+    LevelDefinition {
+        name: "Town".to_string(),
+        size: [TOWN_WIDTH, TOWN_HEIGHT],
+        tile_size: TILE_SIZE,
+        layers: vec![floor, walls],
+        objects: layout.objects,
+        colliders: layout.colliders,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: u64 = 2000;
+
+    #[test]
+    fn every_room_is_reachable() {
+        for seed in 0..SEEDS {
+            let layout = build_layout(&mut GameSeed(seed).rng(RNG_STREAM));
+            assert!(unreachable_rooms(&layout).is_empty(), "seed {} left rooms unreachable", seed);
+        }
+    }
+
+    #[test]
+    fn every_seed_generates_a_town() {
+        for seed in 0..SEEDS {
+            let level = generate_town(&GameSeed(seed)).unwrap();
+            assert!(level.objects.iter().any(|object| matches!(object, LevelObject::PlayerSpawn { .. })), "seed {} has no spawn", seed);
+            assert!(level.objects.iter().any(|object| matches!(object, LevelObject::Extraction { .. })), "seed {} has no way out", seed);
+        }
+    }
+
+    #[test]
+    fn walled_off_rooms_are_caught() {
+        let mut layout = build_layout(&mut GameSeed(0).rng(RNG_STREAM));
+        for cell in layout.cells.iter_mut().filter(|cell| **cell == DOOR) {
+            *cell = WALL;
+        }
+        assert!(!unreachable_rooms(&layout).is_empty());
+    }
+}
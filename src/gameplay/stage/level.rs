@@ -0,0 +1,548 @@
+use avian2d::{math::*, prelude::*};
+use bevy::{asset::RenderAssetUsages, mesh::PrimitiveTopology, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::gameplay::inventory::inventory::{Container, Inventory};
+use crate::gameplay::item::items::{spawn_loot, DetectedLoot, Item, Loot};
+use crate::gameplay::npc::npc::NpcSpawn;
+use crate::gameplay::player::{player::Player, setup::Layer};
+use crate::gameplay::raid::raid::{ExtractionZone, EXTRACTION_TIME};
+use crate::gameplay::random::random::GameSeed;
+use crate::gameplay::save::save::{ContainerSave, LootSave};
+use crate::gameplay::stage::camera::{CameraRig, MapBounds};
+use crate::gameplay::stage::generator::{generate_town, GENERATED_LEVEL};
+use crate::gameplay::stage::world::spawn_tile_layer;
+use crate::gameplay::state::state::GameState;
+
+// Levels live next to the item data and are read the same way, see docs/levels.md for the format
+const LEVEL_DIR: &str = "assets/levels";
+pub const DEFAULT_LEVEL: &str = "saloon";
+
+const OBSTACLE_COLOR: Color = Color::srgb(0.7, 0.7, 0.8);
+const CRATE_COLOR: Color = Color::srgb(0.0, 0.4, 0.7);
+const CONTAINER_COLOR: Color = Color::srgb(0.45, 0.3, 0.15);
+const EXIT_COLOR: Color = Color::srgba(0.9, 0.75, 0.3, 0.35);
+const EXTRACTION_COLOR: Color = Color::srgba(0.2, 0.9, 0.3, 0.35);
+
+// --- DATA ---
+#[derive(Resource, Clone, Debug, Deserialize, Serialize)]
+pub struct LevelDefinition {
+    pub name: String,
+    pub size: [u32; 2], // In tiles
+    pub tile_size: f32, // Grid spacing in pixels
+    #[serde(default)]
+    pub layers: Vec<TileLayer>,
+    #[serde(default)]
+    pub objects: Vec<LevelObject>,
+    #[serde(default)]
+    pub colliders: Vec<ColliderShape>,
+}
+
+impl LevelDefinition {
+    // First PlayerSpawn wins, the map centre otherwise
+    pub fn player_spawn(&self) -> Vec2 {
+        self.objects
+            .iter()
+            .find_map(|object| match object {
+                LevelObject::PlayerSpawn { position } => Some(Vec2::from_array(*position)),
+                _ => None,
+            })
+            .unwrap_or(Vec2::ZERO)
+    }
+
+    // Where a door from another level puts the player, the spawn point when the id is unknown
+    pub fn entry(&self, id: &str) -> Vec2 {
+        self.objects
+            .iter()
+            .find_map(|object| match object {
+                LevelObject::Entry { id: entry, position } if entry == id => Some(Vec2::from_array(*position)),
+                _ => None,
+            })
+            .unwrap_or_else(|| self.player_spawn())
+    }
+
+    // The map is centred on the origin
+    pub fn half_extents(&self) -> Vec2 {
+        Vec2::new(self.size[0] as f32, self.size[1] as f32) * self.tile_size * 0.5
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TileLayer {
+    pub name: String,
+    #[serde(default)]
+    pub tileset: Option<String>, // Shorthand for a single kind used by every filled cell
+    #[serde(default)]
+    pub tiles: BTreeMap<char, TileKind>, // Symbol in rows to the kind of tile it draws
+    #[serde(default)]
+    pub z: f32,
+    #[serde(default)]
+    pub fill: Option<char>, // Every cell is this symbol, rows are ignored
+    #[serde(default)]
+    pub rows: Vec<String>, // Top row first, '.' or ' ' is empty
+}
+
+impl TileLayer {
+    // Cell lookup with y counted up from the bottom row, like TilePos
+    pub fn cell(&self, x: u32, y: u32, height: u32) -> Option<char> {
+        if let Some(symbol) = self.fill {
+            return Some(symbol);
+        }
+
+        let row = height.checked_sub(y + 1).and_then(|row| self.rows.get(row as usize))?;
+        row.chars().nth(x as usize).filter(|symbol| !matches!(symbol, '.' | ' '))
+    }
+
+    pub fn kind(&self, symbol: char) -> Option<TileKind> {
+        self.tiles.get(&symbol).cloned().or_else(|| {
+            self.tileset.as_ref().map(|image| TileKind { images: vec![image.clone()], solid: false, transitions: Vec::new() })
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TileKind {
+    pub images: Vec<String>, // Variants under assets/, one is picked per cell from the seed
+    #[serde(default)]
+    pub solid: bool, // Walls, which get colliders generated and block navigation
+    #[serde(default)]
+    pub transitions: Vec<TileTransition>,
+}
+
+// Auto-tiling rule: a cell bordering next_to on any side draws these images instead
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TileTransition {
+    pub next_to: char,
+    pub images: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum LevelObject {
+    PlayerSpawn { position: [f32; 2] },
+    Loot { item: String, #[serde(default = "single")] stack: u8, position: [f32; 2] },
+    Container { id: String, position: [f32; 2], size: [f32; 2], #[serde(default)] items: Vec<Item> },
+    Crate { position: [f32; 2], size: [f32; 2] }, // Dynamic, the player can push it
+    Exit { to: String, entry: String, position: [f32; 2], size: [f32; 2] }, // Walking in loads `to` at its Entry `entry`
+    Entry { id: String, position: [f32; 2] },
+    Extraction { position: [f32; 2], size: [f32; 2], #[serde(default = "extraction_time")] seconds: f32 },
+    Npc { archetype: String, position: [f32; 2], #[serde(default)] patrol: Vec<[f32; 2]> }, // Walks the patrol points in order, then loops
+}
+
+fn single() -> u8 {
+    1
+}
+
+fn extraction_time() -> f32 {
+    EXTRACTION_TIME
+}
+
+// Static collision, drawn as flat shapes until the art exists
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ColliderShape {
+    Rectangle { position: [f32; 2], size: [f32; 2] },
+    Triangle { position: [f32; 2], points: [[f32; 2]; 3] }, // Points relative to position
+    Circle { position: [f32; 2], radius: f32 },
+}
+
+// Floor loot and container contents as the player left them, in place of what the level holds
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LevelState {
+    pub loot: Vec<LootSave>,
+    pub containers: Vec<ContainerSave>,
+}
+
+// --- RESOURCES ---
+// Levels walked out of during this raid, so coming back finds them looted. Never holds the current one
+#[derive(Resource, Debug, Default)]
+pub struct VisitedLevels {
+    pub levels: BTreeMap<String, LevelState>,
+}
+
+// The level the player is in, which changes as they walk through exits
+#[derive(Resource, Debug)]
+pub struct CurrentLevel {
+    pub name: String,
+}
+
+impl Default for CurrentLevel {
+    fn default() -> Self {
+        Self { name: DEFAULT_LEVEL.to_string() }
+    }
+}
+
+// The level every raid starts in, whichever one the last raid ended in.
+// A file in assets/levels, or `town` for a generated one
+#[derive(Resource, Debug)]
+pub struct StartingLevel {
+    pub name: String,
+}
+
+impl Default for StartingLevel {
+    fn default() -> Self {
+        Self { name: DEFAULT_LEVEL.to_string() }
+    }
+}
+
+// Why the last raid could not start, shown in the hub until one does
+#[derive(Resource, Debug)]
+pub struct LevelError {
+    pub message: String,
+}
+
+// --- COMPONENTS ---
+// Anything solid that blocks movement and sight
+#[derive(Component)]
+pub struct Obstacle;
+
+// Everything a level spawns, despawned together when the player leaves for another level
+#[derive(Component)]
+pub struct LevelEntity;
+
+#[derive(Component)]
+pub struct Exit {
+    pub to: String,
+    pub entry: String,
+}
+
+// --- MESSAGES ---
+// Swaps the current level for another. The player entity stays, so its inventory, health and stamina carry over
+#[derive(Message)]
+pub struct ChangeLevel {
+    pub level: String,
+    pub entry: Option<String>, // The level's PlayerSpawn when None
+}
+
+// --- SYSTEM SETS ---
+// Builds the level on entering the game. Systems that place things in it run after this set
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LevelSystems;
+
+pub fn read_level(name: &str) -> Result<LevelDefinition, String> {
+    let path = format!("{}/{}.ron", LEVEL_DIR, name);
+    let contents = std::fs::read_to_string(&path).map_err(|error| format!("{}: {}", path, error))?;
+    ron::from_str(&contents).map_err(|error| format!("{}: {}", path, error))
+}
+
+// A file in assets/levels, or the generated town
+pub fn resolve_level(name: &str, seed: &GameSeed) -> Result<LevelDefinition, String> {
+    if name == GENERATED_LEVEL {
+        generate_town(seed)
+    }
+    else {
+        read_level(name)
+    }
+}
+
+// What the current level's floor loot and containers hold right now
+pub fn capture_level(
+    loot_query: &Query<(&Item, &Transform), (With<Loot>, Without<Player>)>,
+    container_query: &Query<(&Container, &Inventory), Without<Player>>,
+) -> LevelState {
+    LevelState {
+        loot: loot_query
+            .iter()
+            .map(|(item, transform)| LootSave {
+                item: item.clone(),
+                position: transform.translation.truncate().to_array(),
+            })
+            .collect(),
+        containers: container_query
+            .iter()
+            .map(|(container, inventory)| ContainerSave {
+                id: container.id.clone(),
+                inventory: inventory.clone(),
+            })
+            .collect(),
+    }
+}
+
+// Spawns a level's tiles, colliders and objects, then makes it the current definition.
+// A saved or remembered state replaces the floor loot and container contents the level places
+pub fn build_level(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    asset_server: &AssetServer,
+    seed: &GameSeed,
+    mut level: LevelDefinition,
+    state: Option<&LevelState>,
+) {
+    if let Some(state) = state {
+        level.objects.retain(|object| !matches!(object, LevelObject::Loot { .. }));
+        for object in &mut level.objects {
+            if let LevelObject::Container { id, items, .. } = object {
+                if let Some(saved) = state.containers.iter().find(|saved| saved.id == *id) {
+                    *items = saved.inventory.items.clone();
+                }
+            }
+        }
+    }
+
+    for (index, layer) in level.layers.iter().enumerate() {
+        spawn_tile_layer(commands, asset_server, &level, layer, index, seed);
+    }
+
+    for shape in &level.colliders {
+        spawn_collider(commands, meshes, materials, shape);
+    }
+
+    for object in &level.objects {
+        spawn_object(commands, asset_server, object);
+    }
+
+    if let Some(state) = state {
+        for loot in &state.loot {
+            spawn_loot(commands, asset_server, loot.item.clone(), Vec2::from_array(loot.position));
+        }
+    }
+
+    let half_extents = level.half_extents();
+    commands.insert_resource(MapBounds { min: -half_extents, max: half_extents });
+    commands.insert_resource(level);
+}
+
+// Wall colliders are children of their tilemap and go with it
+pub fn unload_level(commands: &mut Commands, level_query: &Query<Entity, With<LevelEntity>>) {
+    for entity in level_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+// --- SYSTEMS ---
+pub fn load_level(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    asset_server: Res<AssetServer>,
+    start: Res<StartingLevel>,
+    mut current: ResMut<CurrentLevel>,
+    seed: Res<GameSeed>,
+    mut visited: ResMut<VisitedLevels>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Every raid starts from fresh levels, back where raids begin
+    visited.levels.clear();
+    current.name = start.name.clone();
+    match resolve_level(&current.name, &seed) {
+        Ok(level) => {
+            build_level(&mut commands, &mut meshes, &mut materials, &asset_server, &seed, level, None);
+            commands.remove_resource::<LevelError>();
+        }
+        // Nothing to play in, so the raid is called off before the player is spawned
+        Err(error) => {
+            println!("Failed to load level {}", error);
+            commands.insert_resource(LevelError { message: format!("Failed to load level {}", error) });
+            next_state.set(GameState::Hub);
+        }
+    }
+}
+
+// The next raid's level may fail to load, so nothing describing this one may outlive the raid
+pub fn clear_level(mut commands: Commands) {
+    commands.remove_resource::<LevelDefinition>();
+    commands.remove_resource::<MapBounds>();
+}
+
+pub fn change_level(
+    mut commands: Commands,
+    mut change_reader: MessageReader<ChangeLevel>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    asset_server: Res<AssetServer>,
+    mut current: ResMut<CurrentLevel>,
+    seed: Res<GameSeed>,
+    mut detected: ResMut<DetectedLoot>,
+    mut visited: ResMut<VisitedLevels>,
+    level_query: Query<Entity, With<LevelEntity>>,
+    loot_query: Query<(&Item, &Transform), (With<Loot>, Without<Player>)>,
+    container_query: Query<(&Container, &Inventory), Without<Player>>,
+    player: Single<(&mut Transform, &mut LinearVelocity), With<Player>>,
+    mut rig_query: Query<&mut CameraRig>,
+) {
+    let Some(request) = change_reader.read().last() else {
+        return;
+    };
+
+    let level = match resolve_level(&request.level, &seed) {
+        Ok(level) => level,
+        Err(error) => {
+            println!("Failed to load level {}", error);
+            return;
+        }
+    };
+    let arrival = match &request.entry {
+        Some(entry) => level.entry(entry),
+        None => level.player_spawn(),
+    };
+
+    // Remembered before it is unloaded, so walking back in cannot restock it
+    visited.levels.insert(current.name.clone(), capture_level(&loot_query, &container_query));
+    let state = visited.levels.remove(&request.level);
+
+    unload_level(&mut commands, &level_query);
+    build_level(&mut commands, &mut meshes, &mut materials, &asset_server, &seed, level, state.as_ref());
+    current.name = request.level.clone();
+
+    // Detections point at loot that was just despawned
+    detected.items.clear();
+    detected.index = 0;
+
+    let (mut transform, mut velocity) = player.into_inner();
+    transform.translation.x = arrival.x;
+    transform.translation.y = arrival.y;
+    velocity.0 = Vec2::ZERO;
+
+    // Cut to the new room instead of panning across the map
+    for mut rig in &mut rig_query {
+        rig.focus = arrival;
+    }
+}
+
+// Observer on exits, the player's own collider walking in starts the transition
+fn use_exit(
+    event: On<CollisionStart>,
+    exit_query: Query<&Exit>,
+    player_query: Query<(), With<Player>>,
+    mut change_writer: MessageWriter<ChangeLevel>,
+) {
+    let Ok(exit) = exit_query.get(event.collider1) else {
+        return;
+    };
+
+    if player_query.contains(event.collider2) {
+        change_writer.write(ChangeLevel { level: exit.to.clone(), entry: Some(exit.entry.clone()) });
+    }
+}
+
+fn spawn_collider(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    shape: &ColliderShape,
+) {
+    let (position, collider, mesh) = match shape {
+        ColliderShape::Rectangle { position, size } => (
+            position,
+            Collider::rectangle(size[0] as Scalar, size[1] as Scalar),
+            Mesh::from(Rectangle::new(size[0], size[1])),
+        ),
+        ColliderShape::Triangle { position, points } => {
+            let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_POSITION,
+                points.iter().map(|[x, y]| [*x, *y, 0.0]).collect::<Vec<_>>(),
+            );
+            let [a, b, c] = points.map(|[x, y]| Vector::new(x as Scalar, y as Scalar));
+            (position, Collider::triangle(a, b, c), mesh)
+        }
+        ColliderShape::Circle { position, radius } => (
+            position,
+            Collider::circle(*radius as Scalar),
+            Mesh::from(Circle::new(*radius)),
+        ),
+    };
+
+    commands.spawn((
+        Obstacle,
+        Mesh2d(meshes.add(mesh)),
+        MeshMaterial2d(materials.add(OBSTACLE_COLOR)),
+        Transform::from_xyz(position[0], position[1], 1.0),
+        RigidBody::Static,
+        collider,
+        LevelEntity,
+        DespawnOnExit(GameState::InGame),
+    ));
+}
+
+fn spawn_object(commands: &mut Commands, asset_server: &AssetServer, object: &LevelObject) {
+    match object {
+        // Read by spawn_player and change_level
+        LevelObject::PlayerSpawn { .. } | LevelObject::Entry { .. } => {}
+        LevelObject::Loot { item, stack, position } => {
+            spawn_loot(commands, asset_server, Item { id: item.clone(), stack: *stack, insured: false }, Vec2::from_array(*position));
+        }
+        LevelObject::Container { id, position, size, items } => {
+            commands.spawn((
+                Container { id: id.clone() },
+                Inventory { items: items.clone() },
+                Obstacle,
+                Sprite {
+                    color: CONTAINER_COLOR,
+                    custom_size: Some(Vec2::from_array(*size)),
+                    ..default()
+                },
+                Transform::from_xyz(position[0], position[1], 1.0),
+                RigidBody::Static,
+                Collider::rectangle(size[0] as Scalar, size[1] as Scalar),
+                LevelEntity,
+                DespawnOnExit(GameState::InGame),
+            ));
+        }
+        LevelObject::Crate { position, size } => {
+            commands.spawn((
+                Sprite {
+                    color: CRATE_COLOR,
+                    custom_size: Some(Vec2::from_array(*size)),
+                    ..default()
+                },
+                Transform::from_xyz(position[0], position[1], 1.0),
+                RigidBody::Dynamic,
+                Collider::rectangle(size[0] as Scalar, size[1] as Scalar),
+                LinearDamping(4.0), // Comes to rest after a shove
+                LevelEntity,
+                DespawnOnExit(GameState::InGame),
+            ));
+        }
+        LevelObject::Exit { to, entry, position, size } => {
+            commands
+                .spawn((
+                    Exit { to: to.clone(), entry: entry.clone() },
+                    Sprite {
+                        color: EXIT_COLOR,
+                        custom_size: Some(Vec2::from_array(*size)),
+                        ..default()
+                    },
+                    Transform::from_xyz(position[0], position[1], 1.0),
+                    RigidBody::Static,
+                    Collider::rectangle(size[0] as Scalar, size[1] as Scalar),
+                    CollisionEventsEnabled,
+                    // Own layer, so movement sweeps and hitscans pass through it
+                    CollisionLayers::new([Layer::Trigger], [Layer::Default]),
+                    Sensor,
+                    LevelEntity,
+                    DespawnOnExit(GameState::InGame),
+                ))
+                .observe(use_exit);
+        }
+        LevelObject::Extraction { position, size, seconds } => {
+            commands.spawn((
+                ExtractionZone { seconds: *seconds },
+                Sprite {
+                    color: EXTRACTION_COLOR,
+                    custom_size: Some(Vec2::from_array(*size)),
+                    ..default()
+                },
+                Transform::from_xyz(position[0], position[1], 1.0),
+                RigidBody::Static,
+                Collider::rectangle(size[0] as Scalar, size[1] as Scalar),
+                CollidingEntities::default(), // Read by the raid to see who is standing inside
+                CollisionLayers::new([Layer::Trigger], [Layer::Default]),
+                Sensor,
+                LevelEntity,
+                DespawnOnExit(GameState::InGame),
+            ));
+        }
+        LevelObject::Npc { archetype, position, patrol } => {
+            // The NPC plugin fills in the rest from the archetype
+            commands.spawn((
+                NpcSpawn {
+                    archetype: archetype.clone(),
+                    patrol: patrol.iter().map(|point| Vec2::from_array(*point)).collect(),
+                },
+                Transform::from_xyz(position[0], position[1], 2.0),
+                LevelEntity,
+                DespawnOnExit(GameState::InGame),
+            ));
+        }
+    }
+}
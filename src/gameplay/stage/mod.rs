@@ -1,3 +1,6 @@
 pub mod camera;
+pub mod generator;
+pub mod level;
 pub mod stage; 
+pub mod walls;
 pub mod world; 
\ No newline at end of file
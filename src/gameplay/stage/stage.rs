@@ -1,16 +1,28 @@
 use bevy::prelude::*; 
-use super::{camera::*, world::*};
+use super::{camera::*, level::*, walls::*};
+use crate::gameplay::state::state::{GameState, InGameState};
+
+pub struct StagePlugin {
+    pub level: Option<String>, // Level every raid starts in, the default one when missing
+}
 
-pub struct StagePlugin; 
 impl Plugin for StagePlugin {
     fn build(&self, app: &mut App) {
         app 
-            .add_systems(Startup, (
-                init_environment,
-                init_camera,
-            ))
+            .init_resource::<CinematicCamera>()
+            .init_resource::<VisitedLevels>()
+            .init_resource::<CurrentLevel>()
+            .insert_resource(self.level.clone().map(|name| StartingLevel { name }).unwrap_or_default())
+            .add_message::<CameraShake>()
+            .add_message::<ChangeLevel>()
+            .add_systems(Startup, init_camera)
+            .add_systems(OnEnter(GameState::InGame), load_level.in_set(LevelSystems))
+            .add_systems(OnExit(GameState::InGame), clear_level)
+            .add_systems(Update, (zoom_camera, change_level).run_if(in_state(InGameState::Playing)))
             .add_systems(Update, (
+                add_trauma,
                 update_camera,
-            ));
+            ).chain().run_if(in_state(GameState::InGame)))
+            .add_systems(Update, rebuild_wall_colliders.run_if(in_state(GameState::InGame)));
     }
 }
\ No newline at end of file
@@ -0,0 +1,145 @@
+use avian2d::{math::*, prelude::*};
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use crate::gameplay::stage::level::Obstacle;
+
+// --- COMPONENTS ---
+// Tile that is a wall. Colliders follow its tilemap's TileStorage
+#[derive(Component)]
+pub struct SolidTile;
+
+// Generated collider covering a run of wall tiles, child of its tilemap
+#[derive(Component)]
+pub struct WallCollider;
+
+// Covers the filled cells with as few rectangles as possible, walking rows bottom-up and
+// growing each rectangle right first, then up while the whole span stays filled.
+// Returned as min..max corners in tiles.
+pub fn merge_solid_cells(solid: &[bool], width: u32, height: u32) -> Vec<URect> {
+    let index = |x: u32, y: u32| (y * width + x) as usize;
+    let mut covered = vec![false; solid.len()];
+    let mut rects = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            if !solid[index(x, y)] || covered[index(x, y)] {
+                continue;
+            }
+
+            let mut run = 1;
+            while x + run < width && solid[index(x + run, y)] && !covered[index(x + run, y)] {
+                run += 1;
+            }
+
+            let mut rise = 1;
+            while y + rise < height
+                && (x..x + run).all(|cx| solid[index(cx, y + rise)] && !covered[index(cx, y + rise)])
+            {
+                rise += 1;
+            }
+
+            for cy in y..y + rise {
+                for cx in x..x + run {
+                    covered[index(cx, cy)] = true;
+                }
+            }
+            rects.push(URect::new(x, y, x + run, y + rise));
+        }
+    }
+
+    rects
+}
+
+// --- SYSTEMS ---
+// Runs when a tilemap is spawned and whenever its tiles are set or removed
+pub fn rebuild_wall_colliders(
+    mut commands: Commands,
+    tilemap_query: Query<(Entity, &TileStorage, &TilemapSize, &TilemapGridSize), Changed<TileStorage>>,
+    solid_tiles: Query<(), With<SolidTile>>,
+    wall_query: Query<(Entity, &ChildOf), With<WallCollider>>,
+) {
+    for (tilemap, storage, size, grid_size) in &tilemap_query {
+        for (wall, child_of) in &wall_query {
+            if child_of.parent() == tilemap {
+                commands.entity(wall).despawn();
+            }
+        }
+
+        let mut solid = vec![false; (size.x * size.y) as usize];
+        for y in 0..size.y {
+            for x in 0..size.x {
+                solid[(y * size.x + x) as usize] = storage.get(&TilePos { x, y }).is_some_and(|tile| solid_tiles.contains(tile));
+            }
+        }
+
+        // Centred anchor, so the grid starts half the map away from the tilemap origin
+        let cell = Vec2::new(grid_size.x, grid_size.y);
+        let origin = -Vec2::new(size.x as f32, size.y as f32) * cell * 0.5;
+
+        for rect in merge_solid_cells(&solid, size.x, size.y) {
+            let extent = rect.size().as_vec2() * cell;
+            let center = origin + rect.min.as_vec2() * cell + extent * 0.5;
+            let wall = commands
+                .spawn((
+                    WallCollider,
+                    Obstacle,
+                    RigidBody::Static,
+                    Collider::rectangle(extent.x as Scalar, extent.y as Scalar),
+                    Transform::from_xyz(center.x, center.y, 0.0),
+                ))
+                .id();
+            commands.entity(tilemap).add_child(wall);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rows top first like level files, '#' is solid
+    fn grid(rows: &[&str]) -> (Vec<bool>, u32, u32) {
+        let width = rows[0].len() as u32;
+        let height = rows.len() as u32;
+        let solid = rows.iter().rev().flat_map(|row| row.chars().map(|symbol| symbol == '#')).collect();
+        (solid, width, height)
+    }
+
+    // Every solid cell is in exactly one rectangle and no empty cell is in any
+    fn assert_covers(rows: &[&str]) -> Vec<URect> {
+        let (solid, width, height) = grid(rows);
+        let rects = merge_solid_cells(&solid, width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let hits = rects.iter().filter(|rect| (rect.min.x..rect.max.x).contains(&x) && (rect.min.y..rect.max.y).contains(&y)).count();
+                let expected = if solid[(y * width + x) as usize] { 1 } else { 0 };
+                assert_eq!(hits, expected, "cell ({}, {}) in {:?}", x, y, rects);
+            }
+        }
+        rects
+    }
+
+    #[test]
+    fn a_full_rectangle_is_one_collider() {
+        let rects = assert_covers(&["####", "####", "####"]);
+        assert_eq!(rects, vec![URect::new(0, 0, 4, 3)]);
+    }
+
+    #[test]
+    fn an_l_shape_is_two_colliders() {
+        let rects = assert_covers(&["#...", "#...", "####"]);
+        assert_eq!(rects.len(), 2);
+    }
+
+    #[test]
+    fn holes_stay_open() {
+        assert_covers(&["#####", "#.#.#", "#####"]);
+        assert_covers(&["###", "#.#", "###"]);
+        assert_covers(&["#.#.", ".#.#", "#.#."]);
+    }
+
+    #[test]
+    fn an_empty_grid_has_no_colliders() {
+        assert!(assert_covers(&["...", "..."]).is_empty());
+    }
+}
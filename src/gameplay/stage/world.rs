@@ -1,43 +1,146 @@
-use avian2d::{math::*, prelude::*}; 
-use bevy::{
-    asset::RenderAssetUsages,
-    mesh::PrimitiveTopology, prelude::*,
-};
+use std::{collections::BTreeMap, fs::File, io::Read};
+use bevy::prelude::*;
 use bevy_ecs_tilemap::prelude::*;
+use crate::gameplay::random::random::GameSeed;
+use crate::gameplay::stage::level::{LevelDefinition, LevelEntity, TileLayer};
+use crate::gameplay::stage::walls::SolidTile;
+use crate::gameplay::state::state::GameState;
 
-pub fn init_environment(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let texture_handle: Handle<Image> = asset_server.load("floors/floor4_no_hole.png");
-    let map_size = TilemapSize { x: 32, y: 16 };
-    let tilemap_entity = commands.spawn_empty().id();
-    let mut tile_storage = TileStorage::empty(map_size);
+const ASSET_DIR: &str = "assets";
+const LAYER_Z_STEP: f32 = 0.01; // Keeps layers without an explicit z in file order
+const GROUP_Z_STEP: f32 = 0.001; // Keeps the tilemaps of one layer from z-fighting
+
+// Tiles sharing an image size, drawn by one tilemap
+#[derive(Default)]
+struct TileGroup {
+    images: Vec<String>,
+    tiles: Vec<(TilePos, u32, bool)>, // Position, texture index and whether the tile is solid
+}
+
+// Width and height from a PNG's IHDR chunk. Tilemaps need them before the asset server has loaded anything
+fn png_size(path: &str) -> Option<UVec2> {
+    let mut header = [0u8; 24];
+    File::open(format!("{}/{}", ASSET_DIR, path)).ok()?.read_exact(&mut header).ok()?;
+    if &header[1..4] != b"PNG" || &header[12..16] != b"IHDR" {
+        return None;
+    }
+
+    let width = u32::from_be_bytes(header[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(header[20..24].try_into().ok()?);
+    Some(UVec2::new(width, height))
+}
+
+// Picks the image for one cell: a transition when a listed neighbour borders it, else a seeded variant.
+// The same seed always draws the same floor
+fn pick_image(layer: &TileLayer, index: usize, x: u32, y: u32, size: UVec2, seed: &GameSeed) -> Option<String> {
+    let symbol = layer.cell(x, y, size.y)?;
+    let kind = layer.kind(symbol)?;
+
+    let neighbours = [(-1, 0), (1, 0), (0, -1), (0, 1)].map(|(dx, dy): (i32, i32)| {
+        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+        if nx < 0 || ny < 0 || nx >= size.x as i32 || ny >= size.y as i32 {
+            return None;
+        }
+        layer.cell(nx as u32, ny as u32, size.y)
+    });
+
+    let images = kind
+        .transitions
+        .iter()
+        .find(|transition| neighbours.contains(&Some(transition.next_to)))
+        .map(|transition| &transition.images)
+        .unwrap_or(&kind.images);
+    if images.is_empty() {
+        return None;
+    }
+
+    let stream = ((index as u64) << 48) ^ ((x as u64) << 24) ^ y as u64;
+    let choice = seed.rng(stream).next_u64() % images.len() as u64;
+    Some(images[choice as usize].clone())
+}
+
+// A layer becomes one tilemap per image size, since TilemapTexture::Vector needs equal sizes.
+// Each tilemap is scaled so its tiles still land on the level's grid
+pub fn spawn_tile_layer(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    level: &LevelDefinition,
+    layer: &TileLayer,
+    index: usize,
+    seed: &GameSeed,
+) {
+    let map_size = TilemapSize { x: level.size[0], y: level.size[1] };
+    let level_tile = UVec2::splat(level.tile_size as u32);
+    let mut groups: BTreeMap<(u32, u32), TileGroup> = BTreeMap::new();
+    let mut image_sizes: BTreeMap<String, UVec2> = BTreeMap::new();
 
     for x in 0..map_size.x {
         for y in 0..map_size.y {
-            let tile_pos = TilePos { x, y };
-            let tile_entity = commands
-                .spawn(TileBundle {
-                    position: tile_pos,
-                    tilemap_id: TilemapId(tilemap_entity),
-                    ..Default::default()
+            let Some(image) = pick_image(layer, index, x, y, UVec2::new(map_size.x, map_size.y), seed) else {
+                continue;
+            };
+
+            let image_size = *image_sizes.entry(image.clone()).or_insert_with(|| {
+                png_size(&image).unwrap_or_else(|| {
+                    println!("Could not read the size of {}, assuming {}px", image, level.tile_size);
+                    level_tile
                 })
-                .id();
-            tile_storage.set(&tile_pos, tile_entity);
+            });
+            let group = groups.entry((image_size.x, image_size.y)).or_default();
+            let texture_index = match group.images.iter().position(|existing| *existing == image) {
+                Some(position) => position,
+                None => {
+                    group.images.push(image);
+                    group.images.len() - 1
+                }
+            };
+            let solid = layer.cell(x, y, map_size.y).and_then(|symbol| layer.kind(symbol)).is_some_and(|kind| kind.solid);
+            group.tiles.push((TilePos { x, y }, texture_index as u32, solid));
         }
     }
 
-    let tile_size = TilemapTileSize { x: 200.0, y: 200.0 };
-    let grid_size = tile_size.into();
-    let map_type = TilemapType::default();
-
-    commands.entity(tilemap_entity).insert(TilemapBundle {
-        grid_size,
-        map_type,
-        size: map_size,
-        storage: tile_storage,
-        texture: TilemapTexture::Single(texture_handle),
-        tile_size,
-        anchor: TilemapAnchor::Center,
-        ..Default::default()
-    });
-}
+    for (group_index, ((width, height), group)) in groups.into_iter().enumerate() {
+        let tilemap_entity = commands.spawn((LevelEntity, DespawnOnExit(GameState::InGame))).id();
+        let mut tile_storage = TileStorage::empty(map_size);
 
+        for (tile_pos, texture_index, solid) in group.tiles {
+            let mut tile = commands
+                .spawn((
+                    TileBundle {
+                        position: tile_pos,
+                        tilemap_id: TilemapId(tilemap_entity),
+                        texture_index: TileTextureIndex(texture_index),
+                        ..Default::default()
+                    },
+                    LevelEntity,
+                    DespawnOnExit(GameState::InGame),
+                ));
+            if solid {
+                tile.insert(SolidTile);
+            }
+            tile_storage.set(&tile_pos, tile.id());
+        }
+
+        let tile_size = TilemapTileSize { x: width as f32, y: height as f32 };
+        let grid_size = tile_size.into();
+        let map_type = TilemapType::default();
+        let scale = Vec3::new(level.tile_size / width as f32, level.tile_size / height as f32, 1.0);
+        let z = layer.z + index as f32 * LAYER_Z_STEP + group_index as f32 * GROUP_Z_STEP;
+        let textures = group.images.iter().map(|image| asset_server.load(image.as_str())).collect();
+
+        commands.entity(tilemap_entity).insert((
+            Name::new(layer.name.clone()),
+            TilemapBundle {
+                grid_size,
+                map_type,
+                size: map_size,
+                storage: tile_storage,
+                texture: TilemapTexture::Vector(textures),
+                tile_size,
+                anchor: TilemapAnchor::Center,
+                transform: Transform::from_xyz(0.0, 0.0, z).with_scale(scale),
+                ..Default::default()
+            },
+        ));
+    }
+}
@@ -0,0 +1,2 @@
+pub mod stash;
+pub mod ui;
@@ -0,0 +1,221 @@
+use std::{fs, io, path::PathBuf};
+use bevy::prelude::*;
+use crate::gameplay::inventory::inventory::{GridSize, Inventory};
+use crate::gameplay::item::items::{ItemRegistry, ItemType};
+use crate::gameplay::replay::replay::in_replayed_raid;
+use crate::gameplay::save::save::{read_stash, stash_path, write_stash, SaveError, StashSave};
+use crate::gameplay::stash::ui::*;
+use crate::gameplay::state::state::GameState;
+
+pub const STASH_GRID: GridSize = GridSize { columns: 10, rows: 20 };
+
+pub struct StashPlugin;
+impl Plugin for StashPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<StashFile>()
+            .init_resource::<StashView>()
+            .init_resource::<HubFocus>()
+            .add_systems(Startup, load_stash)
+            .add_systems(OnEnter(GameState::Hub), spawn_hub)
+            .add_systems(Update, (
+                navigate_hub,
+                click_hub_item,
+                update_hub,
+            ).chain().run_if(in_state(GameState::Hub)))
+            // A replayed raid plays with the recorded loadout and settles into a stash that is put back
+            // afterwards, so none of it may reach the disk
+            .add_systems(Update, save_stash.run_if(
+                resource_changed::<Stash>.or(resource_changed::<Loadout>).and(not(in_replayed_raid)),
+            ));
+    }
+}
+
+// --- ENUMS ---
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StashSort {
+    #[default]
+    Type,
+    Name,
+    Weight, // Heaviest first
+}
+
+impl StashSort {
+    pub fn next(self) -> Self {
+        match self {
+            StashSort::Type => StashSort::Name,
+            StashSort::Name => StashSort::Weight,
+            StashSort::Weight => StashSort::Type,
+        }
+    }
+}
+
+// --- RESOURCES ---
+// Items kept between raids, packed by the same shape-grid rules as the player's inventory
+#[derive(Resource, Default, Debug)]
+pub struct Stash {
+    pub inventory: Inventory,
+}
+
+// What the player carries into the next raid, handed to their Inventory when it starts
+#[derive(Resource, Default, Debug)]
+pub struct Loadout {
+    pub inventory: Inventory,
+}
+
+// Where the stash is kept on disk
+#[derive(Resource, Debug)]
+pub struct StashFile {
+    pub path: PathBuf,
+    pub locked: bool, // Set when a file that failed to load could not be moved aside, so it is never written over
+}
+
+impl StashFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, locked: false }
+    }
+}
+
+impl Default for StashFile {
+    fn default() -> Self {
+        Self::new(stash_path())
+    }
+}
+
+// How the hub lists the stash. Filtering only hides items, sorting reorders the stash itself
+#[derive(Resource, Default, Debug)]
+pub struct StashView {
+    pub filter: Option<ItemType>,
+    pub sort: StashSort,
+}
+
+impl StashView {
+    pub fn next_filter(&mut self) {
+        self.filter = match self.filter {
+            None => Some(ItemType::Consumable),
+            Some(ItemType::Consumable) => Some(ItemType::Essential),
+            Some(ItemType::Essential) => Some(ItemType::Equipment),
+            Some(ItemType::Equipment) => None,
+        };
+    }
+
+    // Stash indexes that pass the filter, in stash order
+    pub fn visible(&self, stash: &Inventory, registry: &ItemRegistry) -> Vec<usize> {
+        stash
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| match self.filter {
+                Some(item_type) => registry.get(&item.id).is_some_and(|definition| definition.item_type == item_type),
+                None => true,
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+// Moves one entry across, refused when its shape has no room in the receiving grid
+pub fn transfer(
+    from: &mut Inventory,
+    to: &mut Inventory,
+    index: usize,
+    registry: &ItemRegistry,
+    grid: GridSize,
+) -> bool {
+    let Some(item) = from.items.get(index) else {
+        return false;
+    };
+    if !to.fits(item, registry, grid) {
+        return false;
+    }
+
+    let item = from.items.remove(index);
+    to.add(item);
+    true
+}
+
+// Items missing from the registry sort last
+pub fn sort_items(inventory: &mut Inventory, registry: &ItemRegistry, sort: StashSort) {
+    inventory.items.sort_by(|a, b| {
+        let (a_def, b_def) = (registry.get(&a.id), registry.get(&b.id));
+        let known = b_def.is_some().cmp(&a_def.is_some());
+        let order = match (sort, a_def, b_def) {
+            (StashSort::Type, Some(a_def), Some(b_def)) => a_def.item_type.cmp(&b_def.item_type),
+            (StashSort::Weight, Some(a_def), Some(b_def)) => b_def.weight.total_cmp(&a_def.weight),
+            _ => std::cmp::Ordering::Equal,
+        };
+        known.then(order).then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+// --- SYSTEMS ---
+fn load_stash(mut commands: Commands, mut file: ResMut<StashFile>) {
+    let data = match read_stash(&file.path) {
+        Ok(data) => data,
+        // First run, nothing stashed yet
+        Err(SaveError::Io(error)) if error.kind() == io::ErrorKind::NotFound => StashSave::default(),
+        // Moved out of the way, or the next save would write over everything still in it
+        Err(error) => {
+            let backup = file.path.with_extension("ron.bak");
+            match fs::rename(&file.path, &backup) {
+                Ok(()) => println!("Failed to load the stash, kept it as {}: {:?}", backup.display(), error),
+                Err(rename_error) => {
+                    println!("Failed to load the stash, it will not be saved: {:?}, {:?}", error, rename_error);
+                    file.locked = true;
+                }
+            }
+            StashSave::default()
+        }
+    };
+
+    commands.insert_resource(Stash { inventory: data.stash });
+    commands.insert_resource(Loadout { inventory: data.loadout });
+}
+
+fn save_stash(stash: Res<Stash>, loadout: Res<Loadout>, file: Res<StashFile>) {
+    // Just read from disk, there is nothing new to write
+    if file.locked || (stash.is_added() && loadout.is_added()) {
+        return;
+    }
+
+    let data = StashSave {
+        stash: stash.inventory.clone(),
+        loadout: loadout.inventory.clone(),
+    };
+
+    if let Err(error) = write_stash(&file.path, &data) {
+        println!("Failed to save the stash: {:?}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+    use bevy::state::app::StatesPlugin;
+    use super::*;
+    use crate::gameplay::item::items::Item;
+
+    #[test]
+    fn an_unreadable_stash_is_set_aside_not_written_over() {
+        let path = env::temp_dir().join(format!("stash_{}.ron", process::id()));
+        let backup = path.with_extension("ron.bak");
+        let broken = "(stash: (items: [(id: \"revolver\", stack: 1";
+        fs::write(&path, broken).unwrap();
+
+        let mut app = App::new();
+        app
+            .add_plugins((MinimalPlugins, StatesPlugin, StashPlugin))
+            .insert_state(GameState::MainMenu)
+            .insert_resource(StashFile::new(path.clone()));
+        app.update();
+        app.world_mut().resource_mut::<Stash>().inventory.add(Item { id: "bandage".to_string(), stack: 1, insured: false });
+        app.update();
+
+        let kept = fs::read_to_string(&backup).unwrap();
+        let saved = read_stash(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&backup).unwrap();
+        assert_eq!(kept, broken);
+        assert_eq!(saved.stash.items.len(), 1);
+    }
+}
@@ -0,0 +1,285 @@
+use bevy::prelude::*;
+use crate::gameplay::input::input::{Action, ActionState};
+use crate::gameplay::inventory::inventory::{Inventory, PLAYER_GRID};
+use crate::gameplay::item::items::{Item, ItemRegistry, ItemType};
+use crate::gameplay::stage::level::LevelError;
+use crate::gameplay::stash::stash::*;
+use crate::gameplay::state::state::GameState;
+
+const TITLE_SIZE: f32 = 30.0;
+const FONT_SIZE: f32 = 15.0;
+const SLOT_COLOR: Color = Color::srgba(0.2, 0.2, 0.2, 0.8);
+const FOCUS_COLOR: Color = Color::srgba(0.3, 0.45, 0.7, 0.9);
+const ERROR_COLOR: Color = Color::srgb(0.9, 0.3, 0.25);
+
+// --- ENUMS ---
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HubPanel {
+    #[default]
+    Stash,
+    Loadout,
+}
+
+// --- RESOURCES ---
+// Entry picked with the d-pad or arrow keys, `index` counts visible entries of the panel
+#[derive(Resource, Default)]
+pub struct HubFocus {
+    pub panel: HubPanel,
+    pub index: usize,
+}
+
+// --- COMPONENTS ---
+#[derive(Component)]
+pub struct HubList(pub HubPanel);
+
+// `index` is the entry's position in the Stash or Loadout inventory, not in the list
+#[derive(Component)]
+pub struct HubItemButton {
+    pub panel: HubPanel,
+    pub index: usize,
+}
+
+#[derive(Component)]
+pub struct HubStatusText;
+
+// --- SYSTEMS ---
+pub fn spawn_hub(mut commands: Commands, asset_server: Res<AssetServer>, level_error: Option<Res<LevelError>>) {
+    let font_handle: Handle<Font> = asset_server.load("fonts/ztn.otf");
+    let text_font = TextFont {
+        font: font_handle.clone(),
+        font_size: FONT_SIZE,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: percent(100),
+                height: percent(100),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(24.0)),
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.08, 0.06, 0.04, 1.0)),
+            DespawnOnExit(GameState::Hub),
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Text::new("STASH"),
+                TextFont {
+                    font: font_handle.clone(),
+                    font_size: TITLE_SIZE,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            // The raid that was just started never got going
+            if let Some(error) = &level_error {
+                root.spawn((Text::new(error.message.clone()), text_font.clone(), TextColor(ERROR_COLOR)));
+            }
+
+            // Stash on the left, the next raid's loadout on the right
+            root.spawn(Node {
+                width: percent(90),
+                flex_grow: 1.0,
+                column_gap: Val::Px(24.0),
+                ..default()
+            })
+            .with_children(|row| {
+                for (panel, label) in [(HubPanel::Stash, "Stash"), (HubPanel::Loadout, "Loadout")] {
+                    row.spawn(Node {
+                        flex_grow: 1.0,
+                        flex_basis: percent(0),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    })
+                    .with_children(|column| {
+                        column.spawn((Text::new(label), text_font.clone(), TextColor(Color::WHITE)));
+                        column.spawn((
+                            Node {
+                                flex_direction: FlexDirection::Column,
+                                row_gap: Val::Px(2.0),
+                                overflow: Overflow::clip_y(),
+                                ..default()
+                            },
+                            HubList(panel),
+                        ));
+                    });
+                }
+            });
+
+            root.spawn((Text::new(""), text_font.clone(), TextColor(Color::srgb(0.8, 0.8, 0.8)), HubStatusText));
+            root.spawn((
+                Text::new("Arrows select, Enter moves an item across, F filter, O sort, Space start the raid, Q main menu"),
+                text_font.clone(),
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+        });
+}
+
+pub fn navigate_hub(
+    action_state: Res<ActionState>,
+    registry: Res<ItemRegistry>,
+    mut view: ResMut<StashView>,
+    mut focus: ResMut<HubFocus>,
+    mut stash: ResMut<Stash>,
+    mut loadout: ResMut<Loadout>,
+) {
+    if action_state.just_pressed(Action::CycleFilter) {
+        view.next_filter();
+    }
+
+    if action_state.just_pressed(Action::SortStash) {
+        view.sort = view.sort.next();
+        sort_items(&mut stash.inventory, &registry, view.sort);
+    }
+
+    let mut panel = focus.panel;
+    let mut index = focus.index;
+    if action_state.just_pressed(Action::NavigateLeft) {
+        panel = HubPanel::Stash;
+    }
+    if action_state.just_pressed(Action::NavigateRight) {
+        panel = HubPanel::Loadout;
+    }
+    if action_state.just_pressed(Action::NavigateUp) {
+        index = index.saturating_sub(1);
+    }
+    if action_state.just_pressed(Action::NavigateDown) {
+        index += 1;
+    }
+
+    let visible = visible_entries(panel, &stash, &loadout, &view, &registry);
+    index = index.min(visible.len().saturating_sub(1));
+    if focus.panel != panel || focus.index != index {
+        focus.panel = panel;
+        focus.index = index;
+    }
+
+    if action_state.just_pressed(Action::Confirm) {
+        if let Some(entry) = visible.get(index) {
+            move_across(panel, *entry, &mut stash, &mut loadout, &registry);
+        }
+    }
+}
+
+pub fn click_hub_item(
+    registry: Res<ItemRegistry>,
+    button_query: Query<(&Interaction, &HubItemButton), Changed<Interaction>>,
+    mut stash: ResMut<Stash>,
+    mut loadout: ResMut<Loadout>,
+) {
+    for (interaction, button) in &button_query {
+        if *interaction == Interaction::Pressed {
+            move_across(button.panel, button.index, &mut stash, &mut loadout, &registry);
+        }
+    }
+}
+
+// Rebuilds both lists whenever an item moves or the view changes
+pub fn update_hub(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    registry: Res<ItemRegistry>,
+    stash: Res<Stash>,
+    loadout: Res<Loadout>,
+    view: Res<StashView>,
+    focus: Res<HubFocus>,
+    list_query: Query<(Entity, Ref<HubList>)>,
+    mut status: Single<&mut Text, With<HubStatusText>>,
+) {
+    let spawned = list_query.iter().any(|(_, list)| list.is_added());
+    if !spawned && !stash.is_changed() && !loadout.is_changed() && !view.is_changed() && !focus.is_changed() {
+        return;
+    }
+
+    let font_handle: Handle<Font> = asset_server.load("fonts/ztn.otf");
+    for (entity, list) in &list_query {
+        let panel = list.0;
+        let inventory = match panel {
+            HubPanel::Stash => &stash.inventory,
+            HubPanel::Loadout => &loadout.inventory,
+        };
+        let visible = visible_entries(panel, &stash, &loadout, &view, &registry);
+
+        commands.entity(entity).despawn_related::<Children>();
+        commands.entity(entity).with_children(|parent| {
+            for (position, index) in visible.into_iter().enumerate() {
+                let focused = focus.panel == panel && focus.index == position;
+                parent.spawn((
+                    Button,
+                    Node {
+                        padding: UiRect::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    BackgroundColor(if focused { FOCUS_COLOR } else { SLOT_COLOR }),
+                    HubItemButton { panel, index },
+                    children![(
+                        Text::new(item_label(&inventory.items[index])),
+                        TextFont {
+                            font: font_handle.clone(),
+                            font_size: FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    )],
+                ));
+            }
+        });
+    }
+
+    let filter = match view.filter {
+        Some(ItemType::Consumable) => "Consumables",
+        Some(ItemType::Essential) => "Essentials",
+        Some(ItemType::Equipment) => "Equipment",
+        None => "All",
+    };
+    status.0 = format!(
+        "Filter: {}   Sort: {:?}   Stash: {} items   Loadout: {} items, {:.1} weight",
+        filter,
+        view.sort,
+        stash.inventory.items.len(),
+        loadout.inventory.items.len(),
+        loadout.inventory.weight(&registry),
+    );
+}
+
+fn visible_entries(
+    panel: HubPanel,
+    stash: &Stash,
+    loadout: &Loadout,
+    view: &StashView,
+    registry: &ItemRegistry,
+) -> Vec<usize> {
+    match panel {
+        HubPanel::Stash => view.visible(&stash.inventory, registry),
+        HubPanel::Loadout => (0..loadout.inventory.items.len()).collect(),
+    }
+}
+
+// The loadout becomes the player's inventory, so it packs into the player's grid
+fn move_across(
+    panel: HubPanel,
+    index: usize,
+    stash: &mut Stash,
+    loadout: &mut Loadout,
+    registry: &ItemRegistry,
+) {
+    let (from, to, grid): (&mut Inventory, &mut Inventory, _) = match panel {
+        HubPanel::Stash => (&mut stash.inventory, &mut loadout.inventory, PLAYER_GRID),
+        HubPanel::Loadout => (&mut loadout.inventory, &mut stash.inventory, STASH_GRID),
+    };
+
+    if !transfer(from, to, index, registry, grid) {
+        println!("No room for that item");
+    }
+}
+
+fn item_label(item: &Item) -> String {
+    format!("{} x{}{}", item.id, item.stack, if item.insured { " (insured)" } else { "" })
+}
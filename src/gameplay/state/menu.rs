@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+use crate::gameplay::item::items::Item;
+use crate::gameplay::raid::raid::{RaidOutcome, RaidSummary};
+use crate::gameplay::state::state::{GameState, InGameState};
+
+const TITLE_SIZE: f32 = 40.0;
+const FONT_SIZE: f32 = 18.0;
+
+// --- SYSTEMS ---
+pub fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let root = spawn_screen(
+        &mut commands,
+        &asset_server,
+        "SALOON",
+        "Press Enter to start",
+        Color::srgba(0.05, 0.05, 0.1, 1.0),
+    );
+    commands.entity(root).insert(DespawnOnExit(GameState::MainMenu));
+}
+
+pub fn spawn_pause_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let root = spawn_screen(
+        &mut commands,
+        &asset_server,
+        "PAUSED",
+        "Esc to resume, Q to abandon the raid",
+        Color::srgba(0.0, 0.0, 0.0, 0.6),
+    );
+    commands.entity(root).insert(DespawnOnExit(InGameState::Paused));
+}
+
+pub fn spawn_raid_summary(mut commands: Commands, asset_server: Res<AssetServer>, summary: Res<RaidSummary>) {
+    let (title, background) = match summary.outcome {
+        RaidOutcome::Extracted => ("EXTRACTED", Color::srgba(0.0, 0.15, 0.05, 0.9)),
+        RaidOutcome::Died => ("YOU DIED", Color::srgba(0.2, 0.0, 0.0, 0.9)),
+        RaidOutcome::OutOfTime => ("OUT OF TIME", Color::srgba(0.2, 0.0, 0.0, 0.9)),
+        RaidOutcome::Abandoned => ("RAID ABANDONED", Color::srgba(0.2, 0.0, 0.0, 0.9)),
+    };
+
+    let seconds = summary.duration.round() as u32;
+    let hint = format!(
+        "Raid lasted {:02}:{:02}\n\nTo the stash: {}\nLost: {}\n\nPress Enter to return to the stash",
+        seconds / 60,
+        seconds % 60,
+        item_list(&summary.kept),
+        item_list(&summary.lost),
+    );
+
+    let root = spawn_screen(&mut commands, &asset_server, title, &hint, background);
+    commands.entity(root).insert(DespawnOnExit(GameState::RaidSummary));
+}
+
+fn item_list(items: &[Item]) -> String {
+    if items.is_empty() {
+        return "nothing".to_string();
+    }
+
+    items
+        .iter()
+        .map(|item| format!("{} x{}", item.id, item.stack))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Full screen overlay with a title and a hint line underneath.
+fn spawn_screen(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    title: &str,
+    hint: &str,
+    background: Color,
+) -> Entity {
+    let font_handle: Handle<Font> = asset_server.load("fonts/ztn.otf");
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: percent(100),
+                height: percent(100),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(background),
+            GlobalZIndex(10),
+            children![
+                (
+                    Text::new(title),
+                    TextFont {
+                        font: font_handle.clone(),
+                        font_size: TITLE_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ),
+                (
+                    Text::new(hint),
+                    TextFont {
+                        font: font_handle.clone(),
+                        font_size: FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                ),
+            ],
+        ))
+        .id()
+}
@@ -0,0 +1,122 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use crate::gameplay::input::{input::{Action, ActionState}, rebind::{capture_binding, not_rebinding}};
+use crate::gameplay::item::items::ItemRegistry;
+use crate::gameplay::raid::raid::{EndRaid, RaidOutcome};
+use crate::gameplay::state::menu::*;
+
+pub struct GameStatePlugin;
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_state::<GameState>()
+            .add_sub_state::<InGameState>()
+            .add_systems(Update, finish_boot.run_if(in_state(GameState::Boot)))
+            .add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
+            .add_systems(Update, start_game.run_if(in_state(GameState::MainMenu)))
+            .add_systems(Update, leave_hub.run_if(in_state(GameState::Hub)))
+            .add_systems(OnEnter(InGameState::Paused), (spawn_pause_menu, pause_physics))
+            .add_systems(OnExit(InGameState::Paused), unpause_physics)
+            .add_systems(Update, (
+                toggle_pause.run_if(in_state(GameState::InGame).and(not_rebinding)),
+                abandon_raid.run_if(in_state(InGameState::Paused).and(not_rebinding)),
+            ).before(capture_binding))
+            .add_systems(OnEnter(GameState::RaidSummary), spawn_raid_summary)
+            .add_systems(Update, return_to_hub.run_if(in_state(GameState::RaidSummary)));
+    }
+}
+
+// --- STATES ---
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    Boot,
+    MainMenu,
+    Hub, // Stash and loadout between raids
+    InGame,
+    RaidSummary, // Every raid ends here, extracted or not
+}
+
+// Only exists while InGame. Gameplay systems run in Playing, menus layered on top of the world use the others.
+#[derive(SubStates, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::InGame)]
+pub enum InGameState {
+    #[default]
+    Playing,
+    Paused,
+    Inventory,
+}
+
+// --- SYSTEMS ---
+// Boot waits on the data every later state depends on.
+fn finish_boot(
+    registry: Option<Res<ItemRegistry>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if registry.is_some() {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn start_game(
+    action_state: Res<ActionState>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if action_state.just_pressed(Action::Confirm) {
+        next_state.set(GameState::Hub);
+    }
+}
+
+fn leave_hub(
+    action_state: Res<ActionState>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if action_state.just_pressed(Action::StartRaid) {
+        next_state.set(GameState::InGame);
+    }
+    else if action_state.just_pressed(Action::QuitToMenu) {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn toggle_pause(
+    action_state: Res<ActionState>,
+    state: Res<State<InGameState>>,
+    mut next_state: ResMut<NextState<InGameState>>,
+) {
+    if !action_state.just_pressed(Action::Pause) {
+        return;
+    }
+
+    match state.get() {
+        InGameState::Playing => next_state.set(InGameState::Paused),
+        InGameState::Paused | InGameState::Inventory => next_state.set(InGameState::Playing),
+    }
+}
+
+// Leaving mid-raid settles like a death, so only insured items make it back to the stash
+fn abandon_raid(
+    action_state: Res<ActionState>,
+    mut end_writer: MessageWriter<EndRaid>,
+) {
+    if action_state.just_pressed(Action::QuitToMenu) {
+        end_writer.write(EndRaid(RaidOutcome::Abandoned));
+    }
+}
+
+fn return_to_hub(
+    action_state: Res<ActionState>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if action_state.just_pressed(Action::Confirm) {
+        next_state.set(GameState::Hub);
+    }
+}
+
+fn pause_physics(mut time: ResMut<Time<Physics>>) {
+    time.pause();
+}
+
+fn unpause_physics(mut time: ResMut<Time<Physics>>) {
+    time.unpause();
+}
@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+
+// Where a modifier comes from. Each source owns at most one modifier per stat,
+// so re-applying an effect replaces it instead of stacking on top of itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ModifierSource {
+    Sprint,
+    Sneak,
+    Encumbrance,
+    Status,
+    Equipment,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Modifier {
+    Additive(f32),
+    Multiplicative(f32),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ModifierStack {
+    modifiers: BTreeMap<ModifierSource, Modifier>,
+}
+
+impl ModifierStack {
+    pub fn set(&mut self, source: ModifierSource, modifier: Modifier) {
+        self.modifiers.insert(source, modifier);
+    }
+
+    pub fn remove(&mut self, source: ModifierSource) {
+        self.modifiers.remove(&source);
+    }
+
+    pub fn contains(&self, source: ModifierSource) -> bool {
+        self.modifiers.contains_key(&source)
+    }
+
+    // Additive modifiers are summed onto the base first, then multipliers are applied.
+    // BTreeMap iterates in source order, so the result never depends on insertion order.
+    pub fn apply(&self, base: f32) -> f32 {
+        let mut additive = 0.0;
+        let mut multiplier = 1.0;
+
+        for modifier in self.modifiers.values() {
+            match modifier {
+                Modifier::Additive(value) => additive += value,
+                Modifier::Multiplicative(value) => multiplier *= value,
+            }
+        }
+
+        ((base + additive) * multiplier).max(0.0)
+    }
+}
@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use crate::gameplay::player::{health::Health, setup::Speed, stamina::Stamina};
+
+pub struct StatsPlugin;
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (
+                recompute_speed,
+                recompute_health,
+                recompute_stamina,
+            ).in_set(StatsSystems));
+    }
+}
+
+// Systems that write modifiers run before this set, systems that read final stats run after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StatsSystems;
+
+// --- SYSTEMS ---
+fn recompute_speed(mut speed_query: Query<&mut Speed, Changed<Speed>>) {
+    for mut speed in &mut speed_query {
+        let value = speed.modifiers.apply(speed.base);
+        if speed.current != value {
+            speed.current = value;
+        }
+    }
+}
+
+fn recompute_health(mut health_query: Query<&mut Health, Changed<Health>>) {
+    for mut health in &mut health_query {
+        let max = health.modifiers.apply(health.base);
+        if health.max != max {
+            health.max = max;
+            health.current = health.current.min(max);
+        }
+    }
+}
+
+fn recompute_stamina(mut stamina_query: Query<&mut Stamina, Changed<Stamina>>) {
+    for mut stamina in &mut stamina_query {
+        let max = stamina.modifiers.apply(stamina.base);
+        if stamina.max != max {
+            stamina.max = max;
+            stamina.current = stamina.current.min(max);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use crate::gameplay::state::state::InGameState;
+
+const FOOTSTEP_INTERVAL: f32 = 0.3; // Seconds between footsteps while moving
+const NOISE_PER_VELOCITY: f32 = 1.2; // Noise radius per unit of velocity
+const SILENT_VELOCITY: f32 = 5.0; // Drifting slower than this makes no sound
+
+pub struct StealthPlugin;
+impl Plugin for StealthPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_message::<Noise>()
+            .add_systems(FixedUpdate, emit_footsteps.run_if(in_state(InGameState::Playing)));
+    }
+}
+
+// --- MESSAGES ---
+// A sound anyone within radius can hear. Louder movement covers more ground. 
+#[derive(Message, Clone, Copy, Debug)]
+pub struct Noise {
+    pub source: Entity,
+    pub position: Vec2,
+    pub radius: f32,
+}
+
+// --- COMPONENTS ---
+// How visible an entity is to sight checks, 1.0 is fully upright. 
+#[derive(Component)]
+pub struct Footprint {
+    pub scale: f32,
+}
+
+impl Default for Footprint {
+    fn default() -> Self {
+        Self { scale: 1.0 }
+    }
+}
+
+// Emits footstep noise on a fixed cadence while the entity moves. 
+#[derive(Component, Default)]
+pub struct NoiseEmitter {
+    pub timer: f32,
+}
+
+// --- SYSTEMS ---
+fn emit_footsteps(
+    mut noise_writer: MessageWriter<Noise>,
+    mut emitter_query: Query<(Entity, &mut NoiseEmitter, &LinearVelocity, &Transform)>,
+    time: Res<Time>,
+) {
+    for (entity, mut emitter, velocity, transform) in &mut emitter_query {
+        let speed = velocity.0.length();
+        if speed < SILENT_VELOCITY {
+            emitter.timer = 0.0;
+            continue;
+        }
+
+        emitter.timer -= time.delta_secs();
+        if emitter.timer > 0.0 {
+            continue;
+        }
+
+        emitter.timer = FOOTSTEP_INTERVAL;
+        noise_writer.write(Noise {
+            source: entity,
+            position: transform.translation.truncate(),
+            radius: speed * NOISE_PER_VELOCITY,
+        });
+    }
+}
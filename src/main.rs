@@ -1,8 +1,8 @@
 use avian2d::{math::*, prelude::*};
-use bevy::{asset::RenderAssetUsages, prelude::*, render::render_resource::PrimitiveTopology};
+use bevy::prelude::*;
 use bevy_ecs_tilemap::TilemapPlugin;
 use crate::gameplay::controller::plugin::*;
-use crate::gameplay::gameplay::GameplayPlugin;
+use crate::gameplay::gameplay::{GameplayPlugin, LaunchOptions};
 
 mod gameplay;
 
@@ -10,134 +10,32 @@ fn main() {
     App::new()
         .add_plugins((
             DefaultPlugins, 
-            PhysicsPlugins::default().with_length_unit(50.0), 
+            PhysicsPlugins::default()
+                .with_length_unit(50.0)
+                .set(PhysicsInterpolationPlugin::interpolate_all()), // Physics steps in FixedUpdate, render between steps
             PlayerControllerPlugin, 
             TilemapPlugin, 
-            GameplayPlugin,
+            GameplayPlugin { options: launch_options() },
         ))
         .insert_resource(ClearColor(Color::srgb(0.05, 0.05, 0.1)))
-        .insert_resource(Gravity(Vector::NEG_Y * 1000.0))
-        .add_systems(Startup, setup)
+        .insert_resource(Gravity(Vector::ZERO)) // Top-down, nothing falls
         .run();
 }
 
-fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    // A cube to move around
-    commands.spawn((
-        Sprite {
-            color: Color::srgb(0.0, 0.4, 0.7),
-            custom_size: Some(Vec2::new(30.0, 30.0)),
-            ..default()
-        },
-        Transform::from_xyz(50.0, -100.0, 1.0),
-        RigidBody::Dynamic,
-        Collider::rectangle(30.0, 30.0),
-        GravityScale(0.0),
-    ));
-
-    // Platforms
-    commands.spawn((
-        Sprite {
-            color: Color::srgb(0.7, 0.7, 0.8),
-            custom_size: Some(Vec2::new(1100.0, 50.0)),
-            ..default()
-        },
-        Transform::from_xyz(0.0, -175.0, 1.0),
-        RigidBody::Static,
-        Collider::rectangle(1100.0, 50.0),
-    ));
-    commands.spawn((
-        Sprite {
-            color: Color::srgb(0.7, 0.7, 0.8),
-            custom_size: Some(Vec2::new(300.0, 25.0)),
-            ..default()
-        },
-        Transform::from_xyz(175.0, -35.0, 1.0),
-        RigidBody::Static,
-        Collider::rectangle(300.0, 25.0),
-    ));
-    commands.spawn((
-        Sprite {
-            color: Color::srgb(0.7, 0.7, 0.8),
-            custom_size: Some(Vec2::new(300.0, 25.0)),
-            ..default()
-        },
-        Transform::from_xyz(-175.0, 0.0, 1.0),
-        RigidBody::Static,
-        Collider::rectangle(300.0, 25.0),
-    ));
-    commands.spawn((
-        Sprite {
-            color: Color::srgb(0.7, 0.7, 0.8),
-            custom_size: Some(Vec2::new(150.0, 80.0)),
-            ..default()
-        },
-        Transform::from_xyz(475.0, -110.0, 1.0),
-        RigidBody::Static,
-        Collider::rectangle(150.0, 80.0),
-    ));
-    commands.spawn((
-        Sprite {
-            color: Color::srgb(0.7, 0.7, 0.8),
-            custom_size: Some(Vec2::new(150.0, 80.0)),
-            ..default()
-        },
-        Transform::from_xyz(-475.0, -110.0, 1.0),
-        RigidBody::Static,
-        Collider::rectangle(150.0, 80.0),
-    ));
-
-    // Ramps
-
-    let mut ramp_mesh = Mesh::new(
-        PrimitiveTopology::TriangleList,
-        RenderAssetUsages::default(),
-    );
-
-    ramp_mesh.insert_attribute(
-        Mesh::ATTRIBUTE_POSITION,
-        vec![[-125.0, 80.0, 0.0], [-125.0, 0.0, 0.0], [125.0, 0.0, 0.0]],
-    );
-
-    let ramp_collider = Collider::triangle(
-        Vector::new(-125.0, 80.0),
-        Vector::NEG_X * 125.0,
-        Vector::X * 125.0,
-    );
-
-    commands.spawn((
-        Mesh2d(meshes.add(ramp_mesh)),
-        MeshMaterial2d(materials.add(Color::srgb(0.4, 0.4, 0.5))),
-        Transform::from_xyz(-275.0, -150.0, 1.0),
-        RigidBody::Static,
-        ramp_collider,
-    ));
-
-    let mut ramp_mesh = Mesh::new(
-        PrimitiveTopology::TriangleList,
-        RenderAssetUsages::default(),
-    );
-
-    ramp_mesh.insert_attribute(
-        Mesh::ATTRIBUTE_POSITION,
-        vec![[20.0, -40.0, 0.0], [20.0, 40.0, 0.0], [-20.0, -40.0, 0.0]],
-    );
-
-    let ramp_collider = Collider::triangle(
-        Vector::new(20.0, -40.0),
-        Vector::new(20.0, 40.0),
-        Vector::new(-20.0, -40.0),
-    );
-
-    commands.spawn((
-        Mesh2d(meshes.add(ramp_mesh)),
-        MeshMaterial2d(materials.add(Color::srgb(0.4, 0.4, 0.5))),
-        Transform::from_xyz(380.0, -110.0, 1.0),
-        RigidBody::Static,
-        ramp_collider,
-    ));
-}
\ No newline at end of file
+// `--level <name>`, `--seed <n>`, `--record` and `--replay <file>`
+fn launch_options() -> LaunchOptions {
+    let args: Vec<String> = std::env::args().collect();
+    let value_after = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+            .cloned()
+    };
+
+    LaunchOptions {
+        level: value_after("--level"),
+        seed: value_after("--seed").and_then(|seed| seed.parse().ok()),
+        record: args.iter().any(|arg| arg == "--record"),
+        replay: value_after("--replay"),
+    }
+}